@@ -0,0 +1,410 @@
+use cosmwasm_std::{CustomQuery, Deps, DepsMut, Order, Record};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::ops::Bound;
+
+/// Read access to the store underlying a `Context`.
+pub trait Backend {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>>;
+
+    /// Iterates over the records in `[start, end)` in the given order.
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'a>;
+
+    fn debug(&self, _msg: &str) {}
+}
+
+/// Write access to the store underlying a `Context`.
+pub trait BackendMut: Backend {
+    fn set(&mut self, key: &[u8], value: &[u8]);
+
+    fn remove(&mut self, key: &[u8]);
+}
+
+impl<'a, C: CustomQuery> Backend for Deps<'a, C> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.storage.get(key)
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        self.storage.range(start, end, order)
+    }
+
+    fn debug(&self, msg: &str) {
+        self.api.debug(msg)
+    }
+}
+
+impl<'a, C: CustomQuery> Backend for DepsMut<'a, C> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.storage.get(key)
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        self.storage.range(start, end, order)
+    }
+
+    fn debug(&self, msg: &str) {
+        self.api.debug(msg)
+    }
+}
+
+impl<'a, C: CustomQuery> BackendMut for DepsMut<'a, C> {
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.storage.set(key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.storage.remove(key)
+    }
+}
+
+impl<B: Backend + ?Sized> Backend for &B {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        (**self).get(key)
+    }
+
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'a> {
+        (**self).range(start, end, order)
+    }
+
+    fn debug(&self, msg: &str) {
+        (**self).debug(msg)
+    }
+}
+
+impl<B: Backend + ?Sized> Backend for &mut B {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        (**self).get(key)
+    }
+
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'a> {
+        (**self).range(start, end, order)
+    }
+
+    fn debug(&self, msg: &str) {
+        (**self).debug(msg)
+    }
+}
+
+impl<B: BackendMut + ?Sized> BackendMut for &mut B {
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        (**self).set(key, value)
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        (**self).remove(key)
+    }
+}
+
+/// An in-memory store, for running the client logic outside of a contract.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryBackend {
+    records: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn records(&self) -> &BTreeMap<Vec<u8>, Vec<u8>> {
+        &self.records
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.records.get(key).cloned()
+    }
+
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'a> {
+        let Some(bounds) = bounds(start, end) else {
+            return Box::new(std::iter::empty());
+        };
+        let records = self
+            .records
+            .range(bounds)
+            .map(|(key, value)| (key.clone(), value.clone()));
+        match order {
+            Order::Ascending => Box::new(records),
+            Order::Descending => Box::new(records.rev()),
+        }
+    }
+}
+
+impl BackendMut for MemoryBackend {
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.records.insert(key.to_vec(), value.to_vec());
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.records.remove(key);
+    }
+}
+
+/// A backend over a read-only store, such as the `Deps` of a query, which keeps the
+/// writes in memory until it is dropped.
+pub struct Scratch<B> {
+    base: B,
+    writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl<B: Backend> Scratch<B> {
+    pub fn new(base: B) -> Self {
+        Self {
+            base,
+            writes: BTreeMap::new(),
+        }
+    }
+}
+
+impl<B: Backend> Backend for Scratch<B> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.writes.get(key) {
+            Some(value) => value.clone(),
+            None => self.base.get(key),
+        }
+    }
+
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'a> {
+        let Some(bounds) = bounds(start, end) else {
+            return Box::new(std::iter::empty());
+        };
+        let writes = self.writes.range(bounds);
+        let writes: WriteIter<'a> = match order {
+            Order::Ascending => Box::new(writes),
+            Order::Descending => Box::new(writes.rev()),
+        };
+        Box::new(MergedRecords {
+            base: self.base.range(start, end, order).peekable(),
+            writes: writes.peekable(),
+            order,
+        })
+    }
+
+    fn debug(&self, msg: &str) {
+        self.base.debug(msg)
+    }
+}
+
+impl<B: Backend> BackendMut for Scratch<B> {
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.writes.insert(key.to_vec(), Some(value.to_vec()));
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.writes.insert(key.to_vec(), None);
+    }
+}
+
+type WriteIter<'a> = Box<dyn Iterator<Item = (&'a Vec<u8>, &'a Option<Vec<u8>>)> + 'a>;
+
+/// The records of the base store of a `Scratch` merged with its writes, both iterated
+/// in the same order.
+struct MergedRecords<'a> {
+    base: Peekable<Box<dyn Iterator<Item = Record> + 'a>>,
+    writes: Peekable<WriteIter<'a>>,
+    order: Order,
+}
+
+impl Iterator for MergedRecords<'_> {
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Record> {
+        loop {
+            let ordering = match (self.base.peek(), self.writes.peek()) {
+                (None, None) => return None,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some((base_key, _)), Some((write_key, _))) => {
+                    let ordering = base_key.as_slice().cmp(write_key.as_slice());
+                    match self.order {
+                        Order::Ascending => ordering,
+                        Order::Descending => ordering.reverse(),
+                    }
+                }
+            };
+            match ordering {
+                Ordering::Less => return self.base.next(),
+                // the write replaces or removes the record of the base store
+                Ordering::Equal => {
+                    self.base.next();
+                }
+                Ordering::Greater => {}
+            }
+            if let Some((key, Some(value))) = self.writes.next() {
+                return Some((key.clone(), value.clone()));
+            }
+        }
+    }
+}
+
+/// Returns the bounds of the keys in `[start, end)`, or `None` if the range is empty.
+fn bounds(start: Option<&[u8]>, end: Option<&[u8]>) -> Option<(Bound<Vec<u8>>, Bound<Vec<u8>>)> {
+    if let (Some(start), Some(end)) = (start, end) {
+        if start >= end {
+            return None;
+        }
+    }
+    let start = start.map_or(Bound::Unbounded, |start| Bound::Included(start.to_vec()));
+    let end = end.map_or(Bound::Unbounded, |end| Bound::Excluded(end.to_vec()));
+    Some((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// A backend counting the records read from its ranges.
+    struct CountingBackend {
+        records: MemoryBackend,
+        read: Cell<usize>,
+    }
+
+    impl Backend for CountingBackend {
+        fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+            self.records.get(key)
+        }
+
+        fn range<'a>(
+            &'a self,
+            start: Option<&[u8]>,
+            end: Option<&[u8]>,
+            order: Order,
+        ) -> Box<dyn Iterator<Item = Record> + 'a> {
+            Box::new(
+                self.records
+                    .range(start, end, order)
+                    .inspect(|_| self.read.set(self.read.get() + 1)),
+            )
+        }
+    }
+
+    #[test]
+    fn test_scratch_range_is_lazy() {
+        let mut records = MemoryBackend::new();
+        for i in 0..100u8 {
+            records.set(&[i], &[i]);
+        }
+        let base = CountingBackend {
+            records,
+            read: Cell::new(0),
+        };
+        let mut scratch = Scratch::new(&base);
+        scratch.remove(&[0]);
+        scratch.set(&[200], &[200]);
+
+        let first = scratch.range(None, None, Order::Ascending).next();
+        assert_eq!(first, Some((vec![1], vec![1])));
+        assert_eq!(base.read.get(), 2);
+
+        base.read.set(0);
+        let last = scratch.range(None, None, Order::Descending).next();
+        assert_eq!(last, Some((vec![200], vec![200])));
+        assert_eq!(base.read.get(), 1);
+    }
+
+    #[test]
+    fn test_scratch() {
+        let mut base = MemoryBackend::new();
+        for key in [b"a", b"b", b"c", b"d"] {
+            base.set(key, key);
+        }
+        let mut scratch = Scratch::new(&base);
+        scratch.set(b"b", b"B");
+        scratch.remove(b"c");
+        scratch.set(b"e", b"e");
+
+        assert_eq!(scratch.get(b"a"), Some(b"a".to_vec()));
+        assert_eq!(scratch.get(b"b"), Some(b"B".to_vec()));
+        assert_eq!(scratch.get(b"c"), None);
+        let keys = |order| {
+            scratch
+                .range(Some(b"b".as_slice()), Some(b"f".as_slice()), order)
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            keys(Order::Ascending),
+            [b"b".to_vec(), b"d".to_vec(), b"e".to_vec()]
+        );
+        assert_eq!(
+            keys(Order::Descending),
+            [b"e".to_vec(), b"d".to_vec(), b"b".to_vec()]
+        );
+        assert_eq!(
+            scratch
+                .range(
+                    Some(b"c".as_slice()),
+                    Some(b"c".as_slice()),
+                    Order::Ascending
+                )
+                .count(),
+            0
+        );
+
+        // removed and written keys at both ends of the range
+        scratch.remove(b"a");
+        scratch.remove(b"d");
+        scratch.set(b"0", b"0");
+        assert_eq!(
+            scratch
+                .range(None, None, Order::Ascending)
+                .collect::<Vec<_>>(),
+            [
+                (b"0".to_vec(), b"0".to_vec()),
+                (b"b".to_vec(), b"B".to_vec()),
+                (b"e".to_vec(), b"e".to_vec()),
+            ]
+        );
+        assert_eq!(
+            scratch
+                .range(None, Some(b"e".as_slice()), Order::Descending)
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>(),
+            [b"b".to_vec(), b"0".to_vec()]
+        );
+
+        // the writes never reach the underlying store
+        drop(scratch);
+        assert_eq!(base.get(b"b"), Some(b"b".to_vec()));
+        assert_eq!(base.get(b"e"), None);
+    }
+}
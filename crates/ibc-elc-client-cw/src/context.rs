@@ -1,76 +1,80 @@
+use crate::backend::{Backend, BackendMut, Scratch};
 use crate::error::{ContractError, WasmLightClientSpecificError};
+use crate::keys::{
+    format_height, is_reserved_key, parse_be_height, parse_consensus_state_key, parse_height,
+    KeyPrefix, StoreKey, CONSENSUS_STATE_PREFIX, ELC_PREFIX, ITERATE_CONSENSUS_STATE_PREFIX,
+    RESERVED_PREFIXES, UPDATE_HISTORY_PREFIX,
+};
+use crate::response::{MisbehaviourEvidence, UpdateHistoryEntry};
 use crate::wasm_client_state::WasmClientState;
 use crate::wasm_consensus_state::WasmConsensusState;
+use core::cell::OnceCell;
 use core::str::FromStr;
-use cosmwasm_std::{Binary, CustomQuery, Deps, DepsMut, Empty, Env, Storage};
+use cosmwasm_std::{from_json, to_json_vec, Binary, CustomQuery, Deps, DepsMut, Env, Order};
+use ibc::core::ics24_host::identifier::ChainId;
 use light_client::types::{Any, ClientId, Height, Time};
 use light_client::Error as LightError;
 use light_client::{ClientReader, HostClientReader, HostContext};
 use prost::Message;
+use std::collections::BTreeMap;
 use store::KVStore;
 
-pub const SUBJECT_PREFIX: &[u8] = b"subject/";
-pub const SUBSTITUTE_PREFIX: &[u8] = b"substitute/";
+/// The number of entries kept in the update history until `MigrateMsg` sets it.
+pub const DEFAULT_UPDATE_HISTORY_SIZE: u64 = 100;
 
-pub const CLIENT_PREFIX: &str = "clients";
-pub const CLIENT_STATE: &str = "clientState";
-pub const CONSENSUS_STATE_PREFIX: &str = "consensusStates";
-pub const PROCESSED_TIME: &str = "processedTime";
-pub const PROCESSED_HEIGHT: &str = "processedHeight";
-
-pub struct Context<'a, C: CustomQuery = Empty> {
-    deps: Option<Deps<'a, C>>,
-    deps_mut: Option<DepsMut<'a, C>>,
+/// The client store of a contract, over a `Backend`.
+///
+/// Writing to the store, or running an ELC over it, requires a `BackendMut`, so that a
+/// context over the read-only store of a query cannot accept writes; queries run over a
+/// `Scratch` backend instead. The writes made by the ELC through `KVStore` are buffered
+/// in the context: they are applied to the backend by `commit`, and discarded with the
+/// context otherwise.
+pub struct Context<B> {
+    backend: B,
     env: Env,
     client_id: ClientId,
     checksum: Option<Binary>,
-    migration_prefix: Option<&'static [u8]>,
+    migration_prefix: KeyPrefix,
+    elc_writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    /// The client state decoded from the store under `migration_prefix`.
+    client_state: OnceCell<Any>,
 }
 
-impl<'a, C: CustomQuery> Context<'a, C> {
+impl<'a, C: CustomQuery> Context<Scratch<Deps<'a, C>>> {
+    /// Returns a context over the read-only store of a query, whose writes are kept in
+    /// memory for the duration of the query.
     pub fn new_ref(deps: Deps<'a, C>, env: Env) -> Self {
-        let client_id = ClientId::from_str(env.contract.address.as_str()).unwrap();
-
-        Self {
-            deps: Some(deps),
-            deps_mut: None,
-            env,
-            client_id,
-            checksum: None,
-            migration_prefix: None,
-        }
+        Self::new(Scratch::new(deps), env)
     }
+}
 
+impl<'a, C: CustomQuery> Context<DepsMut<'a, C>> {
     pub fn new_mut(deps_mut: DepsMut<'a, C>, env: Env) -> Self {
+        Self::new(deps_mut, env)
+    }
+}
+
+impl<B: Backend> Context<B> {
+    pub fn new(backend: B, env: Env) -> Self {
         let client_id = ClientId::from_str(env.contract.address.as_str()).unwrap();
 
         Self {
-            deps: None,
-            deps_mut: Some(deps_mut),
+            backend,
             env,
             client_id,
             checksum: None,
-            migration_prefix: None,
+            migration_prefix: KeyPrefix::None,
+            elc_writes: BTreeMap::new(),
+            client_state: OnceCell::new(),
         }
     }
 
-    pub fn storage_ref(&self) -> &dyn Storage {
-        match (&self.deps, &self.deps_mut) {
-            (Some(ref deps), _) => deps.storage,
-            (_, Some(ref deps)) => deps.storage,
-            _ => panic!("Either deps or deps_mut should be available"),
-        }
-    }
-
-    pub fn storage_mut(&mut self) -> &mut dyn Storage {
-        match self.deps_mut {
-            Some(ref mut deps) => deps.storage,
-            None => panic!("deps_mut should be available"),
-        }
+    pub fn backend(&self) -> &B {
+        &self.backend
     }
 
-    pub fn log(&self, msg: &str) -> Option<()> {
-        self.deps.map(|deps| deps.api.debug(msg))
+    pub fn log(&self, msg: &str) {
+        self.backend.debug(msg)
     }
 
     pub fn client_id(&self) -> &ClientId {
@@ -85,7 +89,7 @@ impl<'a, C: CustomQuery> Context<'a, C> {
         match &self.checksum {
             Some(checksum) => Ok(checksum.clone()),
             None => {
-                let value = self.get_prefixed(CLIENT_STATE.as_bytes())?;
+                let value = self.require_key(&StoreKey::ClientState)?;
                 let any_wasm_client_state = Any::decode(value.as_slice())?;
                 let wasm_client_state: WasmClientState = any_wasm_client_state.try_into()?;
                 Ok(wasm_client_state.checksum.into())
@@ -94,66 +98,485 @@ impl<'a, C: CustomQuery> Context<'a, C> {
     }
 
     pub fn set_subject_prefix(&mut self) {
-        self.migration_prefix = Some(SUBJECT_PREFIX);
+        self.set_migration_prefix(KeyPrefix::Subject);
     }
 
     pub fn set_substitute_prefix(&mut self) {
-        self.migration_prefix = Some(SUBSTITUTE_PREFIX);
+        self.set_migration_prefix(KeyPrefix::Substitute);
+    }
+
+    fn set_migration_prefix(&mut self, prefix: KeyPrefix) {
+        if self.migration_prefix != prefix {
+            self.migration_prefix = prefix;
+            self.client_state.take();
+        }
+    }
+
+    pub fn get_key(&self, key: &StoreKey) -> Option<Vec<u8>> {
+        self.backend.get(&self.migration_prefix.key(key))
+    }
+
+    pub fn require_key(&self, key: &StoreKey) -> Result<Vec<u8>, ContractError> {
+        self.get_key(key).ok_or_else(|| {
+            ContractError::store_key_not_found(key.kind(), self.migration_prefix.key(key))
+        })
+    }
+
+    /// Returns the height at which the client was frozen by a conflicting header, if any.
+    pub fn frozen_height(&self) -> Result<Option<Height>, ContractError> {
+        self.get_key(&StoreKey::FrozenHeight)
+            .map(|value| {
+                let value = String::from_utf8(value)?;
+                parse_height(&value).ok_or_else(|| {
+                    ContractError::generic(format!("invalid frozen height: {value}"))
+                })
+            })
+            .transpose()
+    }
+
+    /// Fails if the client was frozen by a conflicting header.
+    pub fn ensure_not_frozen(&self) -> Result<(), ContractError> {
+        match self.frozen_height()? {
+            Some(height) => Err(ContractError::generic(format!(
+                "client is frozen at height {}",
+                format_height(&height)
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    pub fn misbehaviour_evidence(&self) -> Result<Option<MisbehaviourEvidence>, ContractError> {
+        self.get_key(&StoreKey::MisbehaviourEvidence)
+            .map(|value| from_json(value).map_err(ContractError::from))
+            .transpose()
+    }
+
+    pub fn processed_time(&self, height: &Height) -> Result<Time, ContractError> {
+        let value = self.require_key(&StoreKey::ProcessedTime(*height))?;
+        let time_vec: [u8; 8] = value
+            .as_slice()
+            .try_into()
+            .map_err(|_| ContractError::generic("invalid processed time length"))?;
+        let timestamp = u64::from_be_bytes(time_vec);
+        Time::from_unix_timestamp_nanos(timestamp.into()).map_err(ContractError::generic)
+    }
+
+    /// Returns the host height at which the consensus state at `height` was stored.
+    ///
+    /// Clients stored by a previous version of this contract hold the big-endian
+    /// revision height alone, which is read at revision number 0.
+    pub fn processed_height(&self, height: &Height) -> Result<Height, ContractError> {
+        let value = self.require_key(&StoreKey::ProcessedHeight(*height))?;
+        if let Some(height) = core::str::from_utf8(&value).ok().and_then(parse_height) {
+            return Ok(height);
+        }
+        let revision_height: [u8; 8] = value
+            .as_slice()
+            .try_into()
+            .map_err(|_| ContractError::generic("invalid processed height"))?;
+        Ok(Height::new(0, u64::from_be_bytes(revision_height)))
+    }
+
+    /// Iterates over the heights of the stored consensus states in the given order,
+    /// using the big-endian keyed height index. Both bounds are exclusive.
+    pub fn consensus_state_heights(
+        &self,
+        start_after: Option<Height>,
+        end_before: Option<Height>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Height> + '_> {
+        let (prefix, prefix_end) = self.migration_prefix.range(ITERATE_CONSENSUS_STATE_PREFIX);
+        let start = match start_after {
+            Some(height) => {
+                let mut start = self
+                    .migration_prefix
+                    .key(&StoreKey::IterateConsensusState(height));
+                start.push(0);
+                start
+            }
+            None => prefix.clone(),
+        };
+        let end = match end_before {
+            Some(height) => self
+                .migration_prefix
+                .key(&StoreKey::IterateConsensusState(height)),
+            None => prefix_end,
+        };
+        let heights = self
+            .backend
+            .range(Some(start.as_slice()), Some(end.as_slice()), order)
+            .filter_map(move |(key, _)| parse_be_height(&key[prefix.len()..]));
+        Box::new(heights)
     }
 
-    pub fn prefixed_key(&self, key: impl AsRef<[u8]>) -> Vec<u8> {
-        let mut prefixed_key = Vec::new();
-        prefixed_key.extend_from_slice(self.migration_prefix.unwrap_or(b""));
-        prefixed_key.extend_from_slice(key.as_ref());
-        prefixed_key
+    pub fn latest_consensus_state_height(&self) -> Option<Height> {
+        self.consensus_state_heights(None, None, Order::Descending)
+            .next()
     }
 
-    pub fn get_prefixed(&self, key: impl AsRef<[u8]>) -> Result<Vec<u8>, ContractError> {
-        let prefixed_key = self.prefixed_key(key);
-        self.storage_ref()
-            .get(&prefixed_key)
-            .ok_or(ContractError::generic(format!(
-                "value not found in storage: key={}",
-                String::from_utf8(prefixed_key).unwrap(),
-            )))
+    pub fn earliest_consensus_state_height(&self) -> Option<Height> {
+        self.consensus_state_heights(None, None, Order::Ascending)
+            .next()
     }
 
-    pub fn set_prefixed(&mut self, key: impl AsRef<[u8]>, value: impl AsRef<[u8]>) {
-        let prefixed_key = self.prefixed_key(key);
-        self.storage_mut().set(&prefixed_key, value.as_ref());
+    /// Returns the sequence number of the next entry of the update history.
+    pub fn update_history_sequence(&self) -> Result<u64, ContractError> {
+        Ok(self.get_u64(&StoreKey::UpdateHistorySequence)?.unwrap_or(0))
     }
 
-    pub fn remove_prefixed(&mut self, key: &[u8]) {
-        let prefixed_key = self.prefixed_key(key);
-        self.storage_mut().remove(&prefixed_key);
+    /// Returns the number of entries in the update history.
+    pub fn update_history_count(&self) -> Result<u64, ContractError> {
+        Ok(self.get_u64(&StoreKey::UpdateHistoryCount)?.unwrap_or(0))
+    }
+
+    /// Returns the number of entries kept in the update history.
+    pub fn update_history_size(&self) -> Result<u64, ContractError> {
+        Ok(self
+            .get_u64(&StoreKey::UpdateHistorySize)?
+            .unwrap_or(DEFAULT_UPDATE_HISTORY_SIZE))
+    }
+
+    fn get_u64(&self, key: &StoreKey) -> Result<Option<u64>, ContractError> {
+        self.get_key(key)
+            .map(|value| {
+                let bytes: [u8; 8] = value
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| ContractError::generic(format!("invalid {} value", key.kind())))?;
+                Ok(u64::from_be_bytes(bytes))
+            })
+            .transpose()
+    }
+
+    /// Returns up to `limit` entries of the update history after the sequence
+    /// `start_after`, in the order in which the headers were accepted.
+    pub fn update_history(
+        &self,
+        start_after: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<UpdateHistoryEntry>, ContractError> {
+        let (prefix, prefix_end) = self.migration_prefix.range(UPDATE_HISTORY_PREFIX);
+        let start = match start_after {
+            Some(sequence) => {
+                let mut start = self
+                    .migration_prefix
+                    .key(&StoreKey::UpdateHistory(sequence));
+                start.push(0);
+                start
+            }
+            None => prefix,
+        };
+        self.backend
+            .range(
+                Some(start.as_slice()),
+                Some(prefix_end.as_slice()),
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|(_, value)| from_json(value).map_err(ContractError::from))
+            .collect()
     }
 
     pub fn host_height(&self) -> Height {
-        Height::new(0, self.env.block.height)
+        let revision_number = ChainId::chain_version(&self.env.block.chain_id);
+        Height::new(revision_number, self.env.block.height)
+    }
+}
+
+impl<B: BackendMut> Context<B> {
+    /// Returns the backend, for writes which bypass the key layout of the context.
+    pub fn backend_mut(&mut self) -> &mut B {
+        self.client_state.take();
+        &mut self.backend
+    }
+
+    /// Returns the consensus state stored at `height`, if any.
+    pub fn get_consensus_state(&self, height: &Height) -> Result<Option<Any>, LightError> {
+        if self.get_key(&StoreKey::ConsensusState(*height)).is_none() {
+            return Ok(None);
+        }
+        self.consensus_state(self.client_id(), height).map(Some)
+    }
+
+    /// Returns whether a consensus state different from `any_consensus_state` is already
+    /// stored at `height`.
+    pub fn has_conflicting_consensus_state(
+        &self,
+        height: &Height,
+        any_consensus_state: &Any,
+    ) -> Result<bool, LightError> {
+        let conflicting = self
+            .get_consensus_state(height)?
+            .is_some_and(|stored| stored.encode_to_vec() != any_consensus_state.encode_to_vec());
+        Ok(conflicting)
+    }
+
+    pub fn set_key(&mut self, key: &StoreKey, value: impl AsRef<[u8]>) {
+        self.invalidate(key);
+        let prefixed_key = self.migration_prefix.key(key);
+        self.backend.set(&prefixed_key, value.as_ref());
+    }
+
+    pub fn remove_key(&mut self, key: &StoreKey) {
+        self.invalidate(key);
+        let prefixed_key = self.migration_prefix.key(key);
+        self.backend.remove(&prefixed_key);
+    }
+
+    /// Drops the decoded client state if `key` overwrites it.
+    fn invalidate(&mut self, key: &StoreKey) {
+        if *key == StoreKey::ClientState {
+            self.client_state.take();
+        }
+    }
+
+    /// Applies the writes buffered from the ELC to the backend.
+    pub fn commit(&mut self) {
+        for (key, value) in std::mem::take(&mut self.elc_writes) {
+            match value {
+                Some(value) => self.backend.set(&key, &value),
+                None => self.backend.remove(&key),
+            }
+        }
+    }
+
+    pub fn freeze(&mut self, height: Height) {
+        self.set_key(&StoreKey::FrozenHeight, format_height(&height));
+    }
+
+    pub fn store_misbehaviour_evidence(
+        &mut self,
+        evidence: &MisbehaviourEvidence,
+    ) -> Result<(), ContractError> {
+        self.set_key(&StoreKey::MisbehaviourEvidence, to_json_vec(evidence)?);
+        Ok(())
+    }
+
+    /// Records a header accepted at `height` in the update history, replacing the entry
+    /// of an earlier header at the same height, and evicts the oldest entries beyond
+    /// `update_history_size`.
+    pub fn record_update(
+        &mut self,
+        height: Height,
+        message_type: String,
+    ) -> Result<(), ContractError> {
+        self.remove_update_history(height)?;
+
+        let sequence = self.update_history_sequence()?;
+        self.set_key(
+            &StoreKey::UpdateHistorySequence,
+            (sequence + 1).to_be_bytes(),
+        );
+        let count = self.update_history_count()?;
+        self.set_key(&StoreKey::UpdateHistoryCount, (count + 1).to_be_bytes());
+        let entry = UpdateHistoryEntry {
+            sequence,
+            height: height.into(),
+            host_height: self.host_height().into(),
+            host_timestamp: self.host_timestamp().as_unix_timestamp_nanos().try_into()?,
+            message_type,
+        };
+        self.set_key(&StoreKey::UpdateHistory(sequence), to_json_vec(&entry)?);
+        self.set_key(
+            &StoreKey::UpdateHistoryHeight(height),
+            sequence.to_be_bytes(),
+        );
+
+        self.evict_update_history()
+    }
+
+    /// Sets the number of entries kept in the update history, and evicts the entries
+    /// beyond it.
+    pub fn set_update_history_size(&mut self, size: u64) -> Result<(), ContractError> {
+        self.set_key(&StoreKey::UpdateHistorySize, size.to_be_bytes());
+        self.evict_update_history()
+    }
+
+    fn evict_update_history(&mut self) -> Result<(), ContractError> {
+        let count = self.update_history_count()?;
+        let excess = count.saturating_sub(self.update_history_size()?);
+        if excess == 0 {
+            return Ok(());
+        }
+        let (start, end) = self.migration_prefix.range(UPDATE_HISTORY_PREFIX);
+        let evicted = self
+            .backend
+            .range(
+                Some(start.as_slice()),
+                Some(end.as_slice()),
+                Order::Ascending,
+            )
+            .take(usize::try_from(excess)?)
+            .map(|(key, value)| {
+                let entry: UpdateHistoryEntry = from_json(value)?;
+                Ok((key, Height::from(entry.height)))
+            })
+            .collect::<Result<Vec<_>, ContractError>>()?;
+        for (key, height) in &evicted {
+            self.backend.remove(key);
+            self.remove_key(&StoreKey::UpdateHistoryHeight(*height));
+        }
+        let count = count.saturating_sub(evicted.len() as u64);
+        self.set_key(&StoreKey::UpdateHistoryCount, count.to_be_bytes());
+        Ok(())
+    }
+
+    /// Removes the entry of the update history for the consensus state at `height`,
+    /// so that the history never refers to a pruned consensus state.
+    pub fn remove_update_history(&mut self, height: Height) -> Result<(), ContractError> {
+        let Some(sequence) = self.get_u64(&StoreKey::UpdateHistoryHeight(height))? else {
+            return Ok(());
+        };
+        self.remove_key(&StoreKey::UpdateHistory(sequence));
+        self.remove_key(&StoreKey::UpdateHistoryHeight(height));
+        let count = self.update_history_count()?;
+        self.set_key(
+            &StoreKey::UpdateHistoryCount,
+            count.saturating_sub(1).to_be_bytes(),
+        );
+        Ok(())
+    }
+
+    /// Copies the value of `key` from the store under `from` to the store under `to`.
+    pub fn copy_key(&mut self, from: KeyPrefix, to: KeyPrefix, key: &StoreKey) {
+        self.invalidate(key);
+        match self.backend.get(&from.key(key)) {
+            Some(value) => self.backend.set(&to.key(key), &value),
+            None => self.backend.remove(&to.key(key)),
+        }
+    }
+
+    /// Copies every consensus state, with its processed time and height, from the store
+    /// under `from` to the store under `to`, and indexes it there.
+    pub fn copy_consensus_states(&mut self, from: KeyPrefix, to: KeyPrefix) {
+        let (start, end) = from.range(format!("{CONSENSUS_STATE_PREFIX}/"));
+        let heights = self
+            .backend
+            .range(
+                Some(start.as_slice()),
+                Some(end.as_slice()),
+                Order::Ascending,
+            )
+            .filter_map(|(key, _)| parse_consensus_state_key(&key[from.as_bytes().len()..]))
+            .collect::<Vec<_>>();
+        for height in heights {
+            for key in [
+                StoreKey::ConsensusState(height),
+                StoreKey::ProcessedTime(height),
+                StoreKey::ProcessedHeight(height),
+            ] {
+                self.copy_key(from, to, &key);
+            }
+            self.backend.set(
+                &to.key(&StoreKey::IterateConsensusState(height)),
+                &StoreKey::ConsensusState(height).to_vec(),
+            );
+        }
+    }
+
+    /// Replaces the ELC keys of the store under `to` with those of the store under `from`.
+    pub fn replace_elc_keys(&mut self, from: KeyPrefix, to: KeyPrefix) {
+        let (start, end) = to.range(ELC_PREFIX);
+        let stale_keys = self
+            .backend
+            .range(
+                Some(start.as_slice()),
+                Some(end.as_slice()),
+                Order::Ascending,
+            )
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        for key in stale_keys {
+            self.backend.remove(&key);
+        }
+
+        let (start, end) = from.range(ELC_PREFIX);
+        let records = self
+            .backend
+            .range(
+                Some(start.as_slice()),
+                Some(end.as_slice()),
+                Order::Ascending,
+            )
+            .collect::<Vec<_>>();
+        for (key, value) in records {
+            let key = StoreKey::Elc(key[start.len()..].to_vec());
+            self.backend.set(&to.key(&key), &value);
+        }
+    }
+
+    /// Brings the store of a client instantiated by a previous version of this contract
+    /// up to date: ELC keys written outside of `ELC_PREFIX` are moved under it, and the
+    /// consensus state height index is rebuilt from the stored consensus states.
+    ///
+    /// The ranges of `RESERVED_PREFIXES` are skipped except for the consensus states, so
+    /// the keys already migrated and the update history are not read.
+    pub fn migrate_store(&mut self) {
+        let (start, end) = KeyPrefix::None.range(format!("{CONSENSUS_STATE_PREFIX}/"));
+        let heights = self
+            .backend
+            .range(
+                Some(start.as_slice()),
+                Some(end.as_slice()),
+                Order::Ascending,
+            )
+            .filter_map(|(key, _)| parse_consensus_state_key(&key))
+            .collect::<Vec<_>>();
+        for height in heights {
+            self.set_key(
+                &StoreKey::IterateConsensusState(height),
+                StoreKey::ConsensusState(height).to_vec(),
+            );
+        }
+
+        let mut records = vec![];
+        let mut start = None;
+        for prefix in RESERVED_PREFIXES {
+            let (prefix_start, prefix_end) = KeyPrefix::None.range(prefix);
+            records.extend(self.backend.range(
+                start.as_deref(),
+                Some(prefix_start.as_slice()),
+                Order::Ascending,
+            ));
+            start = Some(prefix_end);
+        }
+        records.extend(self.backend.range(start.as_deref(), None, Order::Ascending));
+        for (key, value) in records {
+            if !is_reserved_key(&key) {
+                self.backend.remove(&key);
+                self.set_key(&StoreKey::Elc(key), value);
+            }
+        }
     }
 }
 
-impl<'a, C: CustomQuery> KVStore for Context<'a, C> {
+impl<B: BackendMut> KVStore for Context<B> {
     fn set(&mut self, key: Vec<u8>, value: Vec<u8>) {
-        self.set_prefixed(key, value)
+        let prefixed_key = self.migration_prefix.key(&StoreKey::Elc(key));
+        self.elc_writes.insert(prefixed_key, Some(value));
     }
 
     fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
-        self.get_prefixed(key).ok()
+        let prefixed_key = self.migration_prefix.key(&StoreKey::Elc(key.to_vec()));
+        match self.elc_writes.get(&prefixed_key) {
+            Some(value) => value.clone(),
+            None => self.backend.get(&prefixed_key),
+        }
     }
 
     fn remove(&mut self, key: &[u8]) {
-        self.remove_prefixed(key)
+        let prefixed_key = self.migration_prefix.key(&StoreKey::Elc(key.to_vec()));
+        self.elc_writes.insert(prefixed_key, None);
     }
 }
 
-impl<'a, C: CustomQuery> HostContext for Context<'a, C> {
+impl<B: Backend> HostContext for Context<B> {
     fn host_timestamp(&self) -> Time {
         Time::from_unix_timestamp_nanos(self.env.block.time.nanos() as u128).unwrap()
     }
 }
 
-impl<'a, C: CustomQuery> ClientReader for Context<'a, C> {
+impl<B: BackendMut> ClientReader for Context<B> {
     fn client_exists(&self, _client_id: &ClientId) -> bool {
         unimplemented!();
     }
@@ -163,9 +586,12 @@ impl<'a, C: CustomQuery> ClientReader for Context<'a, C> {
     }
 
     fn client_state(&self, client_id: &ClientId) -> Result<Any, LightError> {
+        if let Some(any_client_state) = self.client_state.get() {
+            return Ok(any_client_state.clone());
+        }
         let value = self
-            .get(CLIENT_STATE.as_bytes())
-            .ok_or(LightError::client_state_not_found(client_id.clone()))?;
+            .get_key(&StoreKey::ClientState)
+            .ok_or_else(|| LightError::client_state_not_found(client_id.clone()))?;
         let any_wasm_client_state = Any::decode(value.as_slice()).map_err(|e| {
             WasmLightClientSpecificError::NotAnyWasmClientState(e, client_id.clone())
         })?;
@@ -174,21 +600,13 @@ impl<'a, C: CustomQuery> ClientReader for Context<'a, C> {
             .map_err(|e| WasmLightClientSpecificError::NotWasmClientState(e, client_id.clone()))?;
         let any_client_state = Any::decode(wasm_client_state.data.as_slice())
             .map_err(|e| WasmLightClientSpecificError::NotAnyClientState(e, client_id.clone()))?;
-        Ok(any_client_state)
+        Ok(self.client_state.get_or_init(|| any_client_state).clone())
     }
 
     fn consensus_state(&self, client_id: &ClientId, height: &Height) -> Result<Any, LightError> {
-        let path = format!(
-            "{CONSENSUS_STATE_PREFIX}/{}-{}",
-            height.revision_number(),
-            height.revision_height(),
-        );
         let value = self
-            .get(path.as_bytes())
-            .ok_or(LightError::consensus_state_not_found(
-                client_id.clone(),
-                *height,
-            ))?;
+            .get_key(&StoreKey::ConsensusState(*height))
+            .ok_or_else(|| LightError::consensus_state_not_found(client_id.clone(), *height))?;
         let any_wasm_consensus_state = Any::decode(value.as_slice()).map_err(|e| {
             WasmLightClientSpecificError::NotAnyWasmConsensusState(e, client_id.clone(), *height)
         })?;
@@ -204,7 +622,65 @@ impl<'a, C: CustomQuery> ClientReader for Context<'a, C> {
     }
 }
 
-impl<'a, C: CustomQuery> HostClientReader for Context<'a, C> {}
+impl<B: BackendMut> HostClientReader for Context<B> {}
+
+/// Lookups of the neighbouring consensus states in the consensus state height index.
+///
+/// The `HostClientReader` passed to an ELC does not expose them, so an ELC checking the
+/// monotonicity of consensus state timestamps has to be given the `Context` itself.
+pub trait ConsensusStateIndexReader: ClientReader {
+    /// Returns the consensus state with the lowest height greater than `height`.
+    fn next_consensus_state(
+        &self,
+        client_id: &ClientId,
+        height: &Height,
+    ) -> Result<Option<(Height, Any)>, LightError>;
+
+    /// Returns the consensus state with the highest height lower than `height`.
+    fn prev_consensus_state(
+        &self,
+        client_id: &ClientId,
+        height: &Height,
+    ) -> Result<Option<(Height, Any)>, LightError>;
+
+    fn latest_consensus_state_height(&self) -> Option<Height>;
+
+    fn earliest_consensus_state_height(&self) -> Option<Height>;
+}
+
+impl<B: BackendMut> ConsensusStateIndexReader for Context<B> {
+    fn next_consensus_state(
+        &self,
+        client_id: &ClientId,
+        height: &Height,
+    ) -> Result<Option<(Height, Any)>, LightError> {
+        let next = self
+            .consensus_state_heights(Some(*height), None, Order::Ascending)
+            .next();
+        next.map(|height| Ok((height, self.consensus_state(client_id, &height)?)))
+            .transpose()
+    }
+
+    fn prev_consensus_state(
+        &self,
+        client_id: &ClientId,
+        height: &Height,
+    ) -> Result<Option<(Height, Any)>, LightError> {
+        let prev = self
+            .consensus_state_heights(None, Some(*height), Order::Descending)
+            .next();
+        prev.map(|height| Ok((height, self.consensus_state(client_id, &height)?)))
+            .transpose()
+    }
+
+    fn latest_consensus_state_height(&self) -> Option<Height> {
+        Context::latest_consensus_state_height(self)
+    }
+
+    fn earliest_consensus_state_height(&self) -> Option<Height> {
+        Context::earliest_consensus_state_height(self)
+    }
+}
 
 pub trait ExecutionContext: KVStore {
     type Error;
@@ -233,7 +709,7 @@ pub trait ExecutionContext: KVStore {
     fn delete_update_meta(&mut self, height: Height) -> Result<(), Self::Error>;
 }
 
-impl<'a, C: CustomQuery> ExecutionContext for Context<'a, C> {
+impl<B: BackendMut> ExecutionContext for Context<B> {
     type Error = ContractError;
 
     fn store_client_state(
@@ -241,8 +717,6 @@ impl<'a, C: CustomQuery> ExecutionContext for Context<'a, C> {
         latest_height: Height,
         any_client_state: Any,
     ) -> Result<(), Self::Error> {
-        let prefixed_key = self.prefixed_key(CLIENT_STATE);
-
         let wasm_client_state = WasmClientState {
             checksum: self.obtain_checksum()?.into(),
             latest_height: Some(latest_height.into()),
@@ -251,7 +725,10 @@ impl<'a, C: CustomQuery> ExecutionContext for Context<'a, C> {
 
         let any_wasm_client_state = Any::from(wasm_client_state);
 
-        self.set(prefixed_key, any_wasm_client_state.encode_to_vec());
+        self.set_key(
+            &StoreKey::ClientState,
+            any_wasm_client_state.encode_to_vec(),
+        );
 
         Ok(())
     }
@@ -261,31 +738,28 @@ impl<'a, C: CustomQuery> ExecutionContext for Context<'a, C> {
         height: Height,
         any_consensus_state: Any,
     ) -> Result<(), Self::Error> {
-        let prefixed_key = self.prefixed_key(format!(
-            "{CONSENSUS_STATE_PREFIX}/{}-{}",
-            height.revision_number(),
-            height.revision_height(),
-        ));
-
         let wasm_consensus_state = WasmConsensusState {
             data: any_consensus_state.encode_to_vec(),
         };
 
         let any_wasm_consensus_state = Any::from(wasm_consensus_state);
 
-        self.set(prefixed_key, any_wasm_consensus_state.encode_to_vec());
+        self.set_key(
+            &StoreKey::ConsensusState(height),
+            any_wasm_consensus_state.encode_to_vec(),
+        );
+        self.set_key(
+            &StoreKey::IterateConsensusState(height),
+            StoreKey::ConsensusState(height).to_vec(),
+        );
 
         Ok(())
     }
 
     fn delete_consensus_state(&mut self, height: Height) -> Result<(), Self::Error> {
-        let prefixed_key = self.prefixed_key(format!(
-            "{CONSENSUS_STATE_PREFIX}/{}-{}",
-            height.revision_number(),
-            height.revision_height(),
-        ));
-
-        self.remove(&prefixed_key);
+        self.remove_key(&StoreKey::ConsensusState(height));
+        self.remove_key(&StoreKey::IterateConsensusState(height));
+        self.remove_update_history(height)?;
 
         Ok(())
     }
@@ -296,40 +770,264 @@ impl<'a, C: CustomQuery> ExecutionContext for Context<'a, C> {
         host_timestamp: Time,
         host_height: Height,
     ) -> Result<(), Self::Error> {
-        let prefixed_key = self.prefixed_key(format!(
-            "{CONSENSUS_STATE_PREFIX}/{}-{}/{PROCESSED_TIME}",
-            height.revision_number(),
-            height.revision_height(),
-        ));
         let time_vec = u64::try_from(host_timestamp.as_unix_timestamp_nanos())?.to_be_bytes();
-        self.set(prefixed_key, time_vec.into());
+        self.set_key(&StoreKey::ProcessedTime(height), time_vec);
 
-        let prefixed_key = self.prefixed_key(format!(
-            "{CONSENSUS_STATE_PREFIX}/{}-{}/{PROCESSED_HEIGHT}",
-            height.revision_number(),
-            height.revision_height(),
-        ));
-        let revision_height_vec = host_height.revision_height().to_be_bytes();
-        self.set(prefixed_key, revision_height_vec.into());
+        let host_height_vec = format_height(&host_height).into_bytes();
+        self.set_key(&StoreKey::ProcessedHeight(height), host_height_vec);
 
         Ok(())
     }
 
     fn delete_update_meta(&mut self, height: Height) -> Result<(), Self::Error> {
-        let prefixed_key = self.prefixed_key(format!(
-            "{CONSENSUS_STATE_PREFIX}/{}-{}/{PROCESSED_TIME}",
-            height.revision_number(),
-            height.revision_height(),
-        ));
-        self.remove(&prefixed_key);
-
-        let prefixed_key = self.prefixed_key(format!(
-            "{CONSENSUS_STATE_PREFIX}/{}-{}/{PROCESSED_HEIGHT}",
-            height.revision_number(),
-            height.revision_height(),
-        ));
-        self.remove(&prefixed_key);
+        self.remove_key(&StoreKey::ProcessedTime(height));
+        self.remove_key(&StoreKey::ProcessedHeight(height));
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MemoryBackend;
+    use crate::keys::be_height;
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::Addr;
+
+    fn context(chain_id: &str, block_height: u64) -> Context<MemoryBackend> {
+        let mut env = mock_env();
+        env.block.chain_id = chain_id.to_owned();
+        env.block.height = block_height;
+        env.contract.address = Addr::unchecked("08-wasm-0");
+        Context::new(MemoryBackend::new(), env)
+    }
+
+    #[test]
+    fn test_processed_height() {
+        let height = Height::new(0, 5);
+        let mut ctx = context("ibc-1", 10);
+        ctx.store_update_meta(height, ctx.host_timestamp(), ctx.host_height())
+            .unwrap();
+        assert_eq!(ctx.processed_height(&height).unwrap(), Height::new(1, 10));
+
+        // the legacy encoding of the revision height alone
+        ctx.set_key(&StoreKey::ProcessedHeight(height), 10u64.to_be_bytes());
+        assert_eq!(ctx.processed_height(&height).unwrap(), Height::new(0, 10));
+
+        ctx.set_key(&StoreKey::ProcessedHeight(height), b"1-");
+        assert!(ctx.processed_height(&height).is_err());
+    }
+
+    #[test]
+    fn test_consensus_state_index_reader() {
+        let mut ctx = context("ibc-1", 10);
+        let heights = [Height::new(0, 5), Height::new(0, 10), Height::new(1, 2)];
+        for height in heights {
+            let any_consensus_state = Any::new("/test".to_owned(), be_height(&height).to_vec());
+            ctx.store_consensus_state(height, any_consensus_state)
+                .unwrap();
+        }
+
+        let reader: &dyn ConsensusStateIndexReader = &ctx;
+        let client_id = ctx.client_id();
+        let (next, any_consensus_state) = reader
+            .next_consensus_state(client_id, &heights[0])
+            .unwrap()
+            .unwrap();
+        assert_eq!(next, heights[1]);
+        assert_eq!(any_consensus_state.value, be_height(&heights[1]).to_vec());
+        let (prev, _) = reader
+            .prev_consensus_state(client_id, &heights[2])
+            .unwrap()
+            .unwrap();
+        assert_eq!(prev, heights[1]);
+        assert!(reader
+            .next_consensus_state(client_id, &heights[2])
+            .unwrap()
+            .is_none());
+        assert!(reader
+            .prev_consensus_state(client_id, &heights[0])
+            .unwrap()
+            .is_none());
+        assert_eq!(reader.latest_consensus_state_height(), Some(heights[2]));
+        assert_eq!(reader.earliest_consensus_state_height(), Some(heights[0]));
+
+        // the keys of an ELC are never answered from the index
+        ctx.set(b"indexQuery/latest".to_vec(), b"elc value".to_vec());
+        assert_eq!(ctx.get(b"indexQuery/latest"), Some(b"elc value".to_vec()));
+    }
+
+    #[test]
+    fn test_migrate_store() {
+        let mut ctx = context("ibc-1", 10);
+        let height = Height::new(0, 1);
+        for (key, value) in [
+            (StoreKey::ClientState.to_vec(), b"client state".to_vec()),
+            (
+                StoreKey::ConsensusState(height).to_vec(),
+                b"consensus state".to_vec(),
+            ),
+            (StoreKey::ProcessedTime(height).to_vec(), b"time".to_vec()),
+            (b"elc key".to_vec(), b"1".to_vec()),
+            (b"consensusStatesOfElc".to_vec(), b"2".to_vec()),
+        ] {
+            ctx.backend_mut().set(&key, &value);
+        }
+
+        ctx.migrate_store();
+
+        let records = ctx.backend().records();
+        assert_eq!(
+            records.get(&StoreKey::IterateConsensusState(height).to_vec()),
+            Some(&StoreKey::ConsensusState(height).to_vec())
+        );
+        assert_eq!(records.get(b"elc/elc key".as_slice()), Some(&b"1".to_vec()));
+        assert_eq!(
+            records.get(b"elc/consensusStatesOfElc".as_slice()),
+            Some(&b"2".to_vec())
+        );
+        assert!(!records.contains_key(b"elc key".as_slice()));
+        assert!(!records.contains_key(b"consensusStatesOfElc".as_slice()));
+        assert_eq!(records.len(), 6);
+    }
+
+    #[test]
+    fn test_copy_consensus_states_and_replace_elc_keys() {
+        let mut ctx = context("ibc-1", 10);
+        let heights = [Height::new(0, 1), Height::new(0, 2)];
+        for height in heights {
+            for key in [
+                StoreKey::ConsensusState(height),
+                StoreKey::ProcessedTime(height),
+                StoreKey::ProcessedHeight(height),
+            ] {
+                let key = KeyPrefix::Substitute.key(&key);
+                ctx.backend_mut().set(&key, &key);
+            }
+        }
+        let substitute_key = KeyPrefix::Substitute.key(&StoreKey::Elc(b"a".to_vec()));
+        ctx.backend_mut().set(&substitute_key, b"substitute");
+        let stale_key = KeyPrefix::Subject.key(&StoreKey::Elc(b"b".to_vec()));
+        ctx.backend_mut().set(&stale_key, b"subject");
+
+        ctx.copy_consensus_states(KeyPrefix::Substitute, KeyPrefix::Subject);
+        ctx.replace_elc_keys(KeyPrefix::Substitute, KeyPrefix::Subject);
+
+        ctx.set_subject_prefix();
+        assert_eq!(
+            ctx.consensus_state_heights(None, None, Order::Ascending)
+                .collect::<Vec<_>>(),
+            heights
+        );
+        for height in heights {
+            assert_eq!(
+                ctx.get_key(&StoreKey::ProcessedHeight(height)),
+                Some(KeyPrefix::Substitute.key(&StoreKey::ProcessedHeight(height)))
+            );
+        }
+        assert_eq!(
+            ctx.get_key(&StoreKey::Elc(b"a".to_vec())),
+            Some(b"substitute".to_vec())
+        );
+        assert_eq!(ctx.get_key(&StoreKey::Elc(b"b".to_vec())), None);
+    }
+
+    #[test]
+    fn test_ensure_not_frozen() {
+        let mut ctx = context("ibc-1", 10);
+        ctx.ensure_not_frozen().unwrap();
+        ctx.freeze(Height::new(0, 3));
+        assert_eq!(ctx.frozen_height().unwrap(), Some(Height::new(0, 3)));
+        assert!(ctx.ensure_not_frozen().is_err());
+    }
+
+    #[test]
+    fn test_client_state_cache() {
+        let mut ctx = context("ibc-1", 10);
+        ctx.set_checksum(Binary::from(vec![1; 32]));
+        let any_client_state = |data: &[u8]| Any::new("/test".to_owned(), data.to_vec());
+
+        ctx.store_client_state(Height::new(0, 1), any_client_state(b"a"))
+            .unwrap();
+        assert_eq!(
+            ctx.client_state(ctx.client_id()).unwrap(),
+            any_client_state(b"a")
+        );
+        ctx.store_client_state(Height::new(0, 2), any_client_state(b"b"))
+            .unwrap();
+        assert_eq!(
+            ctx.client_state(ctx.client_id()).unwrap(),
+            any_client_state(b"b")
+        );
+
+        ctx.set_subject_prefix();
+        assert!(ctx.client_state(ctx.client_id()).is_err());
+        let key = KeyPrefix::Subject.key(&StoreKey::ClientState);
+        let value = ctx.backend().get(&StoreKey::ClientState.to_vec()).unwrap();
+        ctx.backend_mut().set(&key, &value);
+        assert_eq!(
+            ctx.client_state(ctx.client_id()).unwrap(),
+            any_client_state(b"b")
+        );
+    }
+
+    #[test]
+    fn test_update_history() {
+        let mut ctx = context("ibc-1", 10);
+        let history = |ctx: &Context<MemoryBackend>, start_after| {
+            ctx.update_history(start_after, 10)
+                .unwrap()
+                .into_iter()
+                .map(|entry| (entry.sequence, entry.height.revision_height))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ctx.update_history_size().unwrap(),
+            DEFAULT_UPDATE_HISTORY_SIZE
+        );
+
+        // the oldest entries are evicted, even if a lower height is backfilled later
+        ctx.set_update_history_size(2).unwrap();
+        for height in [3, 4, 1] {
+            ctx.record_update(Height::new(0, height), "/test".to_owned())
+                .unwrap();
+        }
+        assert_eq!(history(&ctx, None), [(1, 4), (2, 1)]);
+        assert_eq!(history(&ctx, Some(1)), [(2, 1)]);
+        assert_eq!(ctx.update_history_count().unwrap(), 2);
+        assert_eq!(
+            ctx.get_key(&StoreKey::UpdateHistoryHeight(Height::new(0, 3))),
+            None
+        );
+
+        // a height recorded again keeps only its latest entry
+        ctx.record_update(Height::new(0, 4), "/test".to_owned())
+            .unwrap();
+        assert_eq!(history(&ctx, None), [(2, 1), (3, 4)]);
+        assert_eq!(ctx.update_history_count().unwrap(), 2);
+
+        ctx.delete_consensus_state(Height::new(0, 4)).unwrap();
+        ctx.delete_consensus_state(Height::new(0, 4)).unwrap();
+        assert_eq!(history(&ctx, None), [(2, 1)]);
+        assert_eq!(ctx.update_history_count().unwrap(), 1);
+
+        ctx.set_update_history_size(0).unwrap();
+        assert!(history(&ctx, None).is_empty());
+        assert_eq!(ctx.update_history_count().unwrap(), 0);
+        assert_eq!(
+            ctx.get_key(&StoreKey::UpdateHistoryHeight(Height::new(0, 1))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_host_height_revision_number_from_chain_id() {
+        assert_eq!(context("ibc-1", 10).host_height(), Height::new(1, 10));
+        assert_eq!(
+            context("evmos_9000-4", 10).host_height(),
+            Height::new(4, 10)
+        );
+        assert_eq!(context("testchain", 10).host_height(), Height::new(0, 10));
+    }
+}
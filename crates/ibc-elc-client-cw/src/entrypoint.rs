@@ -1,18 +1,102 @@
+use crate::backend::BackendMut;
 use crate::context::{Context, ExecutionContext};
 use crate::error::ContractError;
+use crate::keys::{format_height, KeyPrefix, StoreKey};
 use crate::msg::*;
 use crate::response::*;
-use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response};
-use light_client::types::Any;
+use cosmwasm_schema::serde::de::DeserializeOwned;
+use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response};
+use light_client::types::{Any, Height as LcpHeight};
 use light_client::{ClientReader, HostContext, LightClient, UpdateClientResult};
 use prost::Message;
+use std::time::Duration;
+
+const DEFAULT_QUERY_LIMIT: u32 = 10;
+const MAX_QUERY_LIMIT: u32 = 100;
+
+const FROZEN: &str = "Frozen";
 
 pub trait Entrypoint {
     type LightClient: LightClient;
+    type CustomQueryMsg: DeserializeOwned;
 
     fn get_status_from_client_state(any_client_state: Any) -> Result<String, ContractError>;
     fn get_timestamp_from_consensus_state(any_consensus_state: Any) -> Result<u64, ContractError>;
 
+    /// Verifies the client message for `VerifyClientMessage` without updating the state.
+    ///
+    /// A message the ELC rejects is an error.
+    fn verify_client_message<B: BackendMut>(
+        lc: &Self::LightClient,
+        ctx: &Context<B>,
+        any_message: Any,
+    ) -> Result<(), ContractError> {
+        lc.update_client(ctx, ctx.client_id().clone(), any_message)?;
+        Ok(())
+    }
+
+    /// Checks whether the client message is a misbehaviour or a header conflicting with
+    /// a stored consensus state, for `CheckForMisbehaviour`.
+    ///
+    /// Verification is left to `verify_client_message`: a message the ELC rejects is not
+    /// a misbehaviour, so only a client message which is not an `Any` is an error.
+    fn check_for_misbehaviour<B: BackendMut>(
+        lc: &Self::LightClient,
+        ctx: &Context<B>,
+        any_message: Any,
+    ) -> Result<bool, ContractError> {
+        let found_misbehaviour = match lc.update_client(ctx, ctx.client_id().clone(), any_message) {
+            Ok(UpdateClientResult::Misbehaviour(_)) => true,
+            Ok(UpdateClientResult::UpdateState(res)) => {
+                ctx.has_conflicting_consensus_state(&res.height, &res.new_any_consensus_state)?
+            }
+            Err(_) => false,
+        };
+        Ok(found_misbehaviour)
+    }
+
+    /// Handles the client-specific queries merged into the contract's `QueryMsg`.
+    fn custom_query<B: BackendMut>(
+        _lc: &Self::LightClient,
+        _ctx: &Context<B>,
+        _msg: Self::CustomQueryMsg,
+    ) -> Result<Binary, ContractError> {
+        Err(ContractError::unsupported(
+            "custom queries are not supported",
+        ))
+    }
+
+    /// Checks that the substitute client can replace the subject client, and returns
+    /// the client state to store for the subject on `MigrateClientStore`.
+    fn check_substitute_and_update_state(
+        _subject_any_client_state: Any,
+        _substitute_any_client_state: Any,
+    ) -> Result<Any, ContractError> {
+        Err(ContractError::unsupported(
+            "MigrateClientStore is not supported",
+        ))
+    }
+
+    /// Returns the trusting period of the client state for the `ClientHealth` query,
+    /// if the client expires.
+    fn get_trusting_period_from_client_state(
+        _any_client_state: Any,
+    ) -> Result<Option<Duration>, ContractError> {
+        Ok(None)
+    }
+
+    /// Renders the client state as JSON for the `ClientState` query, if supported.
+    fn client_state_to_json(_any_client_state: &Any) -> Result<Option<String>, ContractError> {
+        Ok(None)
+    }
+
+    /// Renders the consensus state as JSON for the `ConsensusState` query, if supported.
+    fn consensus_state_to_json(
+        _any_consensus_state: &Any,
+    ) -> Result<Option<String>, ContractError> {
+        Ok(None)
+    }
+
     fn instantiate(
         lc: &Self::LightClient,
         deps: DepsMut<'_>,
@@ -31,41 +115,108 @@ pub trait Entrypoint {
         ctx.store_client_state(res.height, any_client_state)?;
         ctx.store_consensus_state(res.height, any_consensus_state)?;
         ctx.store_update_meta(res.height, ctx.host_timestamp(), ctx.host_height())?;
+        ctx.commit();
 
         Ok(Response::default().set_data(to_json_binary(&ContractResult::success())?))
     }
 
+    fn migrate(deps: DepsMut<'_>, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+        let mut ctx = Context::new_mut(deps, env);
+        ctx.migrate_store();
+        if let Some(size) = msg.update_history_size {
+            ctx.set_update_history_size(size)?;
+        }
+
+        Ok(Response::default())
+    }
+
     fn sudo(
         lc: &Self::LightClient,
         deps: DepsMut<'_>,
         env: Env,
         msg: SudoMsg,
     ) -> Result<Response, ContractError> {
-        let mut ctx = Context::new_mut(deps, env);
+        Self::sudo_with_context(lc, Context::new_mut(deps, env), msg)
+    }
 
+    /// Handles a sudo message over a context the caller has already read from,
+    /// e.g. to select the light client, so that its decoded client state is reused.
+    fn sudo_with_context<B: BackendMut>(
+        lc: &Self::LightClient,
+        mut ctx: Context<B>,
+        msg: SudoMsg,
+    ) -> Result<Response, ContractError> {
         let result = match msg {
             SudoMsg::UpdateState(msg) => {
                 let any_message = Any::decode(msg.client_message.as_slice())?;
-                let res = match lc.update_client(&ctx, ctx.client_id().clone(), any_message)? {
-                    UpdateClientResult::UpdateState(d) => d,
-                    _ => panic!("unexpected non-UpdateState client message"),
-                };
+                let message_type = any_message.type_url.clone();
+                let (height, any_client_state, any_consensus_state) =
+                    match verify_header(lc, &ctx, any_message)? {
+                        VerifiedHeader::New {
+                            height,
+                            any_client_state,
+                            any_consensus_state,
+                        } => (height, any_client_state, any_consensus_state),
+                        // resubmitting a header for an already stored height is a no-op, so
+                        // the writes buffered by the ELC are not committed
+                        VerifiedHeader::Stored { height, .. } => {
+                            let result = ContractResult::success().heights(vec![height]);
+                            return Ok(Response::default().set_data(to_json_binary(&result)?));
+                        }
+                        VerifiedHeader::Conflicting(height) => {
+                            return Err(ContractError::generic(format!(
+                                "conflicting consensus state at height {}",
+                                format_height(&height),
+                            )));
+                        }
+                        VerifiedHeader::Misbehaviour => {
+                            return Err(ContractError::generic(
+                                "unexpected non-UpdateState client message",
+                            ));
+                        }
+                    };
 
-                ctx.store_client_state(res.height, res.new_any_client_state)?;
-                ctx.store_consensus_state(res.height, res.new_any_consensus_state)?;
-                ctx.store_update_meta(res.height, ctx.host_timestamp(), ctx.host_height())?;
+                ctx.store_client_state(height, any_client_state)?;
+                ctx.store_consensus_state(height, any_consensus_state)?;
+                ctx.store_update_meta(height, ctx.host_timestamp(), ctx.host_height())?;
+                ctx.record_update(height, message_type)?;
 
-                ContractResult::success().heights(vec![res.height])
+                ContractResult::success().heights(vec![height])
             }
             SudoMsg::UpdateStateOnMisbehaviour(msg) => {
                 let any_message = Any::decode(msg.client_message.as_slice())?;
-                let res = match lc.update_client(&ctx, ctx.client_id().clone(), any_message)? {
-                    UpdateClientResult::Misbehaviour(d) => d,
-                    _ => panic!("unexpected non-Misbehaviour client message"),
-                };
+                let frozen_height =
+                    match lc.update_client(&ctx, ctx.client_id().clone(), any_message)? {
+                        UpdateClientResult::Misbehaviour(res) => {
+                            // the ELC freezes its client state at the latest height
+                            let latest_height = lc.latest_height(&ctx, ctx.client_id())?;
+                            ctx.store_client_state(latest_height, res.new_any_client_state)?;
+                            ctx.freeze(latest_height);
+                            latest_height
+                        }
+                        UpdateClientResult::UpdateState(res)
+                            if ctx.has_conflicting_consensus_state(
+                                &res.height,
+                                &res.new_any_consensus_state,
+                            )? =>
+                        {
+                            ctx.freeze(res.height);
+                            res.height
+                        }
+                        _ => {
+                            return Err(ContractError::generic(
+                                "client message is not a misbehaviour",
+                            ));
+                        }
+                    };
 
-                let latest_height = lc.latest_height(&ctx, ctx.client_id())?;
-                ctx.store_client_state(latest_height, res.new_any_client_state)?;
+                let evidence = MisbehaviourEvidence {
+                    client_message: msg.client_message,
+                    host_height: ctx.host_height().into(),
+                    host_timestamp: ctx.host_timestamp().as_unix_timestamp_nanos().try_into()?,
+                    frozen_height: frozen_height.into(),
+                };
+                ctx.store_misbehaviour_evidence(&evidence)?;
 
                 ContractResult::success()
             }
@@ -75,6 +226,7 @@ pub trait Entrypoint {
                 ));
             }
             SudoMsg::VerifyMembership(msg) => {
+                ctx.ensure_not_frozen()?;
                 let (prefix, path) = msg.merkle_path.prefix_and_path()?;
                 let _ = lc.verify_membership(
                     &ctx,
@@ -89,6 +241,7 @@ pub trait Entrypoint {
                 ContractResult::success()
             }
             SudoMsg::VerifyNonMembership(msg) => {
+                ctx.ensure_not_frozen()?;
                 let (prefix, path) = msg.merkle_path.prefix_and_path()?;
                 let _ = lc.verify_non_membership(
                     &ctx,
@@ -101,12 +254,31 @@ pub trait Entrypoint {
 
                 ContractResult::success()
             }
-            SudoMsg::MigrateClientStore(_) => {
-                return Err(ContractError::unsupported(
-                    "MigrateClientStore is not supported",
-                ));
+            SudoMsg::MigrateClientStore(MigrateClientStoreMsg {}) => {
+                ctx.set_subject_prefix();
+                let subject_any_client_state = ctx.client_state(ctx.client_id())?;
+
+                ctx.set_substitute_prefix();
+                let substitute_any_client_state = ctx.client_state(ctx.client_id())?;
+                let latest_height = lc.latest_height(&ctx, ctx.client_id())?;
+
+                let new_any_client_state = Self::check_substitute_and_update_state(
+                    subject_any_client_state,
+                    substitute_any_client_state,
+                )?;
+
+                ctx.copy_consensus_states(KeyPrefix::Substitute, KeyPrefix::Subject);
+                ctx.replace_elc_keys(KeyPrefix::Substitute, KeyPrefix::Subject);
+
+                ctx.set_subject_prefix();
+                ctx.store_client_state(latest_height, new_any_client_state)?;
+                ctx.remove_key(&StoreKey::FrozenHeight);
+                ctx.remove_key(&StoreKey::MisbehaviourEvidence);
+
+                ContractResult::success()
             }
         };
+        ctx.commit();
 
         Ok(Response::default().set_data(to_json_binary(&result)?))
     }
@@ -115,14 +287,30 @@ pub trait Entrypoint {
         lc: &Self::LightClient,
         deps: Deps<'_>,
         env: Env,
-        msg: QueryMsg,
+        msg: ContractQueryMsg<Self::CustomQueryMsg>,
     ) -> Result<Binary, ContractError> {
-        let ctx = Context::new_ref(deps, env);
+        Self::query_with_context(lc, Context::new_ref(deps, env), msg)
+    }
+
+    /// Handles a query over a context the caller has already read from.
+    fn query_with_context<B: BackendMut>(
+        lc: &Self::LightClient,
+        ctx: Context<B>,
+        msg: ContractQueryMsg<Self::CustomQueryMsg>,
+    ) -> Result<Binary, ContractError> {
+        let msg = match msg {
+            ContractQueryMsg::Wasm(msg) => msg,
+            ContractQueryMsg::Custom(msg) => return Self::custom_query(lc, &ctx, msg),
+        };
 
         let retval = match msg {
             QueryMsg::Status(StatusMsg {}) => {
-                let any_client_state = ctx.client_state(ctx.client_id())?;
-                let status = Self::get_status_from_client_state(any_client_state)?;
+                let status = if ctx.frozen_height()?.is_some() {
+                    FROZEN.to_owned()
+                } else {
+                    let any_client_state = ctx.client_state(ctx.client_id())?;
+                    Self::get_status_from_client_state(any_client_state)?
+                };
                 to_json_binary(&StatusResponse { status })?
             }
             QueryMsg::ExportMetadata(ExportMetadataMsg {}) => {
@@ -138,16 +326,711 @@ pub trait Entrypoint {
             }
             QueryMsg::VerifyClientMessage(msg) => {
                 let any_message = Any::decode(msg.client_message.as_slice())?;
-                lc.update_client(&ctx, ctx.client_id().clone(), any_message)?;
+                Self::verify_client_message(lc, &ctx, any_message)?;
                 to_json_binary(&VerifyClientMessageResponse {})?
             }
             QueryMsg::CheckForMisbehaviour(msg) => {
                 let any_message = Any::decode(msg.client_message.as_slice())?;
-                let res = lc.update_client(&ctx, ctx.client_id().clone(), any_message)?;
-                let found_misbehaviour = matches!(res, UpdateClientResult::Misbehaviour(_));
+                let found_misbehaviour = Self::check_for_misbehaviour(lc, &ctx, any_message)?;
                 to_json_binary(&CheckForMisbehaviourResponse { found_misbehaviour })?
             }
+            QueryMsg::UpdateMeta(msg) => {
+                let height = msg.height.into();
+                let processed_time = ctx.processed_time(&height)?;
+                let processed_height = ctx.processed_height(&height)?;
+                to_json_binary(&UpdateMetaResponse {
+                    processed_time: processed_time.as_unix_timestamp_nanos().try_into()?,
+                    processed_height: processed_height.into(),
+                })?
+            }
+            QueryMsg::ConsensusStateHeights(msg) => {
+                let limit = msg
+                    .limit
+                    .unwrap_or(DEFAULT_QUERY_LIMIT)
+                    .min(MAX_QUERY_LIMIT) as usize;
+                let heights = ctx
+                    .consensus_state_heights(
+                        msg.start_after.map(Into::into),
+                        None,
+                        Order::Ascending,
+                    )
+                    .take(limit)
+                    .map(Into::into)
+                    .collect();
+                to_json_binary(&ConsensusStateHeightsResponse { heights })?
+            }
+            QueryMsg::ClientState(ClientStateMsg {}) => {
+                let any_client_state = ctx.client_state(ctx.client_id())?;
+                let json = Self::client_state_to_json(&any_client_state)?;
+                to_json_binary(&ClientStateResponse {
+                    type_url: any_client_state.type_url,
+                    value: any_client_state.value.into(),
+                    json,
+                })?
+            }
+            QueryMsg::ConsensusState(msg) => {
+                let any_consensus_state =
+                    ctx.consensus_state(ctx.client_id(), &msg.height.into())?;
+                let json = Self::consensus_state_to_json(&any_consensus_state)?;
+                to_json_binary(&ConsensusStateResponse {
+                    type_url: any_consensus_state.type_url,
+                    value: any_consensus_state.value.into(),
+                    json,
+                })?
+            }
+            QueryMsg::MisbehaviourEvidence(MisbehaviourEvidenceMsg {}) => {
+                let evidence = ctx.misbehaviour_evidence()?;
+                to_json_binary(&MisbehaviourEvidenceResponse { evidence })?
+            }
+            QueryMsg::SimulateUpdateState(msg) => {
+                let any_message = Any::decode(msg.client_message.as_slice())?;
+                let (height, latest_height, any_consensus_state) =
+                    match verify_header(lc, &ctx, any_message)? {
+                        VerifiedHeader::New {
+                            height,
+                            any_consensus_state,
+                            ..
+                        } => (height, height, any_consensus_state),
+                        // `UpdateState` is a no-op for an already stored height
+                        VerifiedHeader::Stored {
+                            height,
+                            any_consensus_state,
+                        } => (
+                            height,
+                            lc.latest_height(&ctx, ctx.client_id())?,
+                            any_consensus_state,
+                        ),
+                        VerifiedHeader::Conflicting(_) | VerifiedHeader::Misbehaviour => {
+                            return Ok(to_json_binary(&SimulateUpdateStateResponse {
+                                heights: vec![],
+                                latest_height: None,
+                                consensus_state_timestamp: None,
+                                found_misbehaviour: true,
+                            })?);
+                        }
+                    };
+                let timestamp = Self::get_timestamp_from_consensus_state(any_consensus_state)?;
+                to_json_binary(&SimulateUpdateStateResponse {
+                    heights: vec![height.into()],
+                    latest_height: Some(latest_height.into()),
+                    consensus_state_timestamp: Some(timestamp),
+                    found_misbehaviour: false,
+                })?
+            }
+            QueryMsg::ClientHealth(ClientHealthMsg {}) => {
+                let latest_height = lc.latest_height(&ctx, ctx.client_id())?;
+                let any_consensus_state = ctx.consensus_state(ctx.client_id(), &latest_height)?;
+                let latest_timestamp =
+                    Self::get_timestamp_from_consensus_state(any_consensus_state)?;
+                let host_timestamp: u64 =
+                    ctx.host_timestamp().as_unix_timestamp_nanos().try_into()?;
+
+                let time_since_last_update = match ctx.processed_time(&latest_height) {
+                    Ok(processed_time) => {
+                        let processed_time: u64 =
+                            processed_time.as_unix_timestamp_nanos().try_into()?;
+                        Some(host_timestamp.saturating_sub(processed_time))
+                    }
+                    Err(_) => None,
+                };
+                let trusting_period = Self::get_trusting_period_from_client_state(
+                    ctx.client_state(ctx.client_id())?,
+                )?
+                .map(|period| u64::try_from(period.as_nanos()))
+                .transpose()?;
+                let expiry_timestamp =
+                    trusting_period.map(|period| latest_timestamp.saturating_add(period));
+
+                to_json_binary(&ClientHealthResponse {
+                    latest_height: latest_height.into(),
+                    latest_timestamp,
+                    host_timestamp,
+                    time_since_last_update,
+                    trusting_period,
+                    expiry_timestamp,
+                })?
+            }
+            QueryMsg::UpdateHistory(msg) => {
+                let limit = msg
+                    .limit
+                    .unwrap_or(DEFAULT_QUERY_LIMIT)
+                    .min(MAX_QUERY_LIMIT) as usize;
+                let entries = ctx.update_history(msg.start_after, limit)?;
+                to_json_binary(&UpdateHistoryResponse { entries })?
+            }
         };
         Ok(retval)
     }
 }
+
+/// A client message verified for `UpdateState`, without writing to the client store.
+enum VerifiedHeader {
+    /// A header at a height without a consensus state.
+    New {
+        height: LcpHeight,
+        any_client_state: Any,
+        any_consensus_state: Any,
+    },
+    /// A header at a height whose consensus state is already stored, for which
+    /// `UpdateState` is a no-op.
+    Stored {
+        height: LcpHeight,
+        any_consensus_state: Any,
+    },
+    /// A header conflicting with the consensus state stored at its height.
+    Conflicting(LcpHeight),
+    /// A misbehaviour.
+    Misbehaviour,
+}
+
+/// Verifies a client message as `UpdateState` does, for both `UpdateState` and
+/// `SimulateUpdateState`.
+fn verify_header<L: LightClient, B: BackendMut>(
+    lc: &L,
+    ctx: &Context<B>,
+    any_message: Any,
+) -> Result<VerifiedHeader, ContractError> {
+    ctx.ensure_not_frozen()?;
+    let res = match lc.update_client(ctx, ctx.client_id().clone(), any_message)? {
+        UpdateClientResult::UpdateState(res) => res,
+        _ => return Ok(VerifiedHeader::Misbehaviour),
+    };
+
+    let header =
+        if ctx.has_conflicting_consensus_state(&res.height, &res.new_any_consensus_state)? {
+            VerifiedHeader::Conflicting(res.height)
+        } else if ctx.get_key(&StoreKey::ConsensusState(res.height)).is_some() {
+            VerifiedHeader::Stored {
+                height: res.height,
+                any_consensus_state: res.new_any_consensus_state,
+            }
+        } else {
+            VerifiedHeader::New {
+                height: res.height,
+                any_client_state: res.new_any_client_state,
+                any_consensus_state: res.new_any_consensus_state,
+            }
+        };
+    Ok(header)
+}
+
+#[cfg(all(test, feature = "testkit"))]
+mod tests {
+    use super::*;
+    use crate::testkit::mock::{MockEntrypoint, MockFixtures, INITIAL_HEIGHT};
+    use crate::testkit::{env, Fixtures};
+    use cosmwasm_std::testing::{
+        message_info, mock_dependencies, MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{from_json, Addr, OwnedDeps, Record, Storage};
+    use ibc::core::ics02_client::client_state::ClientState as _;
+    use ibc::core::ics02_client::consensus_state::ConsensusState as _;
+    use ibc::mock::client_state::MockClientState;
+    use ibc::mock::consensus_state::MockConsensusState;
+    use light_client::types::proto::google::protobuf::Any as ProtoAny;
+    use light_client::types::Height as LcpHeight;
+    use mock_lc::MockLightClient;
+
+    type MockDeps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+    fn instantiate() -> MockDeps {
+        let mut deps = mock_dependencies();
+        let (msg, block) = MockFixtures.instantiate();
+        let info = message_info(&Addr::unchecked("sender"), &[]);
+        MockEntrypoint::instantiate(&MockLightClient, deps.as_mut(), env(block), info, msg)
+            .unwrap();
+        deps
+    }
+
+    fn update(deps: &mut MockDeps, height: u64) -> Result<Response, ContractError> {
+        let (msg, _) = MockFixtures::update_at(height);
+        sudo(deps, height, SudoMsg::UpdateState(msg))
+    }
+
+    fn sudo(deps: &mut MockDeps, height: u64, msg: SudoMsg) -> Result<Response, ContractError> {
+        MockEntrypoint::sudo(
+            &MockLightClient,
+            deps.as_mut(),
+            env(MockFixtures::block(height)),
+            msg,
+        )
+    }
+
+    fn records(deps: &MockDeps) -> Vec<Record> {
+        deps.storage.range(None, None, Order::Ascending).collect()
+    }
+
+    fn query<T: DeserializeOwned>(
+        deps: &MockDeps,
+        height: u64,
+        msg: QueryMsg,
+    ) -> Result<T, ContractError> {
+        let res = MockEntrypoint::query(
+            &MockLightClient,
+            deps.as_ref(),
+            env(MockFixtures::block(height)),
+            ContractQueryMsg::Wasm(msg),
+        )?;
+        Ok(from_json(res)?)
+    }
+
+    fn consensus_state_heights(
+        deps: &MockDeps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> Vec<u64> {
+        let res: ConsensusStateHeightsResponse = query(
+            deps,
+            INITIAL_HEIGHT,
+            QueryMsg::ConsensusStateHeights(ConsensusStateHeightsMsg {
+                start_after: start_after.map(|height| LcpHeight::new(0, height).into()),
+                limit,
+            }),
+        )
+        .unwrap();
+        res.heights
+            .into_iter()
+            .map(|height| height.revision_height)
+            .collect()
+    }
+
+    #[test]
+    fn test_consensus_state_heights() {
+        let mut deps = instantiate();
+        let last_height = INITIAL_HEIGHT + u64::from(MAX_QUERY_LIMIT) + 1;
+        for height in INITIAL_HEIGHT + 1..=last_height {
+            update(&mut deps, height).unwrap();
+        }
+
+        assert_eq!(
+            consensus_state_heights(&deps, None, None),
+            (INITIAL_HEIGHT..INITIAL_HEIGHT + u64::from(DEFAULT_QUERY_LIMIT)).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            consensus_state_heights(&deps, Some(INITIAL_HEIGHT + 2), Some(2)),
+            [INITIAL_HEIGHT + 3, INITIAL_HEIGHT + 4]
+        );
+        assert_eq!(
+            consensus_state_heights(&deps, None, Some(u32::MAX)).len(),
+            MAX_QUERY_LIMIT as usize
+        );
+        assert_eq!(
+            consensus_state_heights(&deps, Some(last_height - 1), Some(10)),
+            [last_height]
+        );
+        assert!(consensus_state_heights(&deps, Some(last_height), None).is_empty());
+    }
+
+    #[test]
+    fn test_consensus_state_heights_before_migrate() {
+        let mut deps = instantiate();
+        for height in INITIAL_HEIGHT + 1..=INITIAL_HEIGHT + 3 {
+            update(&mut deps, height).unwrap();
+        }
+
+        // a client instantiated before the height index has no index entries
+        for height in INITIAL_HEIGHT..=INITIAL_HEIGHT + 3 {
+            deps.storage
+                .remove(&StoreKey::IterateConsensusState(LcpHeight::new(0, height)).to_vec());
+        }
+        assert!(consensus_state_heights(&deps, None, None).is_empty());
+
+        MockEntrypoint::migrate(
+            deps.as_mut(),
+            env(MockFixtures::block(INITIAL_HEIGHT + 3)),
+            MigrateMsg {
+                update_history_size: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            consensus_state_heights(&deps, None, None),
+            (INITIAL_HEIGHT..=INITIAL_HEIGHT + 3).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_update_meta() {
+        let mut deps = instantiate();
+        let height = INITIAL_HEIGHT + 1;
+        update(&mut deps, height).unwrap();
+
+        let block = MockFixtures::block(height);
+        let res: UpdateMetaResponse = query(
+            &deps,
+            height,
+            QueryMsg::UpdateMeta(UpdateMetaMsg {
+                height: LcpHeight::new(0, height).into(),
+            }),
+        )
+        .unwrap();
+        assert_eq!(res.processed_time, block.time.nanos());
+        assert_eq!(res.processed_height.revision_height, block.height);
+
+        let res = query::<UpdateMetaResponse>(
+            &deps,
+            height,
+            QueryMsg::UpdateMeta(UpdateMetaMsg {
+                height: LcpHeight::new(0, height + 1).into(),
+            }),
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_client_and_consensus_state() {
+        let mut deps = instantiate();
+        let height = INITIAL_HEIGHT + 1;
+        update(&mut deps, height).unwrap();
+
+        let res: ClientStateResponse =
+            query(&deps, height, QueryMsg::ClientState(ClientStateMsg {})).unwrap();
+        assert_eq!(res.json, None);
+        let client_state = MockClientState::try_from(ProtoAny {
+            type_url: res.type_url,
+            value: res.value.to_vec(),
+        })
+        .unwrap();
+        assert_eq!(client_state.latest_height().revision_height(), height);
+
+        let res: ConsensusStateResponse = query(
+            &deps,
+            height,
+            QueryMsg::ConsensusState(ConsensusStateMsg {
+                height: LcpHeight::new(0, height).into(),
+            }),
+        )
+        .unwrap();
+        assert_eq!(res.json, None);
+        let consensus_state = MockConsensusState::try_from(ProtoAny {
+            type_url: res.type_url,
+            value: res.value.to_vec(),
+        })
+        .unwrap();
+        assert_eq!(
+            consensus_state.timestamp().nanoseconds(),
+            MockFixtures::block(height).time.nanos()
+        );
+
+        let res = query::<ConsensusStateResponse>(
+            &deps,
+            height,
+            QueryMsg::ConsensusState(ConsensusStateMsg {
+                height: LcpHeight::new(0, height + 1).into(),
+            }),
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_migrate_client_store() {
+        let mut subject = instantiate();
+        update(&mut subject, INITIAL_HEIGHT + 1).unwrap();
+        let frozen_height = format_height(&LcpHeight::new(0, INITIAL_HEIGHT + 1));
+        subject
+            .storage
+            .set(&StoreKey::FrozenHeight.to_vec(), frozen_height.as_bytes());
+        subject
+            .storage
+            .set(&StoreKey::MisbehaviourEvidence.to_vec(), b"evidence");
+        subject
+            .storage
+            .set(&StoreKey::Elc(b"stale".to_vec()).to_vec(), b"subject");
+
+        let last_height = INITIAL_HEIGHT + 3;
+        let mut substitute = instantiate();
+        for height in INITIAL_HEIGHT + 1..=last_height {
+            update(&mut substitute, height).unwrap();
+        }
+        substitute
+            .storage
+            .set(&StoreKey::Elc(b"key".to_vec()).to_vec(), b"substitute");
+
+        // ibc-go passes both client stores under their prefixes
+        let mut deps = mock_dependencies();
+        for (prefix, store) in [
+            (KeyPrefix::Subject, &subject),
+            (KeyPrefix::Substitute, &substitute),
+        ] {
+            for (key, value) in store.storage.range(None, None, Order::Ascending) {
+                deps.storage
+                    .set(&[prefix.as_bytes(), key.as_slice()].concat(), &value);
+            }
+        }
+        MockEntrypoint::sudo(
+            &MockLightClient,
+            deps.as_mut(),
+            env(MockFixtures::block(last_height)),
+            SudoMsg::MigrateClientStore(MigrateClientStoreMsg {}),
+        )
+        .unwrap();
+
+        let get = |key: &StoreKey| deps.storage.get(&KeyPrefix::Subject.key(key));
+        assert_eq!(get(&StoreKey::FrozenHeight), None);
+        assert_eq!(get(&StoreKey::MisbehaviourEvidence), None);
+        assert_eq!(get(&StoreKey::Elc(b"stale".to_vec())), None);
+        assert_eq!(
+            get(&StoreKey::Elc(b"key".to_vec())),
+            Some(b"substitute".to_vec())
+        );
+
+        let any_wasm_client_state = Any::decode(get(&StoreKey::ClientState).unwrap().as_slice());
+        let wasm_client_state: crate::wasm_client_state::WasmClientState =
+            any_wasm_client_state.unwrap().try_into().unwrap();
+        let latest_height = wasm_client_state.latest_height.unwrap();
+        assert_eq!(latest_height.revision_height, last_height);
+        for height in INITIAL_HEIGHT..=last_height {
+            let height = LcpHeight::new(0, height);
+            for key in [
+                StoreKey::ConsensusState(height),
+                StoreKey::ProcessedTime(height),
+                StoreKey::ProcessedHeight(height),
+            ] {
+                assert_eq!(get(&key), substitute.storage.get(&key.to_vec()), "{key:?}");
+            }
+            assert_eq!(
+                get(&StoreKey::IterateConsensusState(height)),
+                Some(StoreKey::ConsensusState(height).to_vec())
+            );
+        }
+    }
+
+    #[test]
+    fn test_resubmitted_header_is_noop() {
+        let mut deps = instantiate();
+        let height = INITIAL_HEIGHT + 1;
+        update(&mut deps, height).unwrap();
+        let before = records(&deps);
+
+        // resubmitted in a later block, which would change the update metadata
+        let (msg, _) = MockFixtures::update_at(height);
+        let res = sudo(&mut deps, height + 1, SudoMsg::UpdateState(msg)).unwrap();
+        let result: ContractResult = from_json(res.data.unwrap()).unwrap();
+        assert_eq!(result.heights, Some(vec![LcpHeight::new(0, height).into()]));
+        assert_eq!(records(&deps), before);
+
+        let res: UpdateHistoryResponse = query(
+            &deps,
+            height + 1,
+            QueryMsg::UpdateHistory(UpdateHistoryMsg {
+                start_after: None,
+                limit: None,
+            }),
+        )
+        .unwrap();
+        assert_eq!(res.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_conflicting_header() {
+        let mut deps = instantiate();
+        let height = INITIAL_HEIGHT + 1;
+        update(&mut deps, height).unwrap();
+        let (msg, _) = MockFixtures::conflicting_update_at(height);
+
+        let before = records(&deps);
+        assert!(sudo(&mut deps, height, SudoMsg::UpdateState(msg.clone())).is_err());
+        assert_eq!(records(&deps), before);
+
+        let res: CheckForMisbehaviourResponse = query(
+            &deps,
+            height,
+            QueryMsg::CheckForMisbehaviour(CheckForMisbehaviourMsg {
+                client_message: msg.client_message.clone(),
+            }),
+        )
+        .unwrap();
+        assert!(res.found_misbehaviour);
+
+        sudo(
+            &mut deps,
+            height,
+            SudoMsg::UpdateStateOnMisbehaviour(UpdateStateOnMisbehaviourMsg {
+                client_message: msg.client_message,
+            }),
+        )
+        .unwrap();
+        assert_eq!(
+            deps.storage.get(&StoreKey::FrozenHeight.to_vec()),
+            Some(format_height(&LcpHeight::new(0, height)).into_bytes())
+        );
+        let res: StatusResponse = query(&deps, height, QueryMsg::Status(StatusMsg {})).unwrap();
+        assert_eq!(res.status, FROZEN);
+    }
+
+    #[test]
+    fn test_check_for_misbehaviour() {
+        let mut deps = instantiate();
+        let height = INITIAL_HEIGHT + 1;
+        let check = |deps: &MockDeps, client_message: Binary| {
+            query::<CheckForMisbehaviourResponse>(
+                deps,
+                height,
+                QueryMsg::CheckForMisbehaviour(CheckForMisbehaviourMsg { client_message }),
+            )
+            .map(|res| res.found_misbehaviour)
+        };
+
+        let (msg, _) = MockFixtures::update_at(height);
+        assert!(!check(&deps, msg.client_message.clone()).unwrap());
+        update(&mut deps, height).unwrap();
+        assert!(!check(&deps, msg.client_message).unwrap());
+
+        // rejected by the ELC, which `VerifyClientMessage` reports
+        let unknown_any = Any::new("/unknown".to_owned(), vec![]).encode_to_vec();
+        assert!(!check(&deps, unknown_any.clone().into()).unwrap());
+        let res = query::<VerifyClientMessageResponse>(
+            &deps,
+            height,
+            QueryMsg::VerifyClientMessage(VerifyClientMessageMsg {
+                client_message: unknown_any.into(),
+            }),
+        );
+        assert!(res.is_err());
+
+        assert!(check(&deps, b"garbage".to_vec().into()).is_err());
+    }
+
+    #[test]
+    fn test_simulate_update_state() {
+        let mut deps = instantiate();
+        let height = INITIAL_HEIGHT + 1;
+        let simulate = |deps: &MockDeps, client_message: Binary| {
+            query::<SimulateUpdateStateResponse>(
+                deps,
+                height + 1,
+                QueryMsg::SimulateUpdateState(SimulateUpdateStateMsg { client_message }),
+            )
+        };
+
+        // a new height, without writing to the client store
+        let before = records(&deps);
+        let (msg, _) = MockFixtures::update_at(height);
+        let res = simulate(&deps, msg.client_message.clone()).unwrap();
+        assert_eq!(
+            res,
+            SimulateUpdateStateResponse {
+                heights: vec![LcpHeight::new(0, height).into()],
+                latest_height: Some(LcpHeight::new(0, height).into()),
+                consensus_state_timestamp: Some(MockFixtures::block(height).time.nanos()),
+                found_misbehaviour: false,
+            }
+        );
+        assert_eq!(records(&deps), before);
+
+        // an already stored height keeps the latest height
+        update(&mut deps, height).unwrap();
+        update(&mut deps, height + 1).unwrap();
+        let res = simulate(&deps, msg.client_message).unwrap();
+        assert_eq!(res.heights, vec![LcpHeight::new(0, height).into()]);
+        assert_eq!(
+            res.latest_height,
+            Some(LcpHeight::new(0, height + 1).into())
+        );
+        assert!(!res.found_misbehaviour);
+
+        let (msg, _) = MockFixtures::conflicting_update_at(height);
+        let res = simulate(&deps, msg.client_message.clone()).unwrap();
+        assert_eq!(
+            res,
+            SimulateUpdateStateResponse {
+                heights: vec![],
+                latest_height: None,
+                consensus_state_timestamp: None,
+                found_misbehaviour: true,
+            }
+        );
+
+        assert!(simulate(&deps, b"garbage".to_vec().into()).is_err());
+
+        // `UpdateState` fails on a frozen client
+        sudo(
+            &mut deps,
+            height + 1,
+            SudoMsg::UpdateStateOnMisbehaviour(UpdateStateOnMisbehaviourMsg {
+                client_message: msg.client_message,
+            }),
+        )
+        .unwrap();
+        let (msg, _) = MockFixtures::update_at(height + 2);
+        assert!(simulate(&deps, msg.client_message).is_err());
+    }
+
+    /// The mock entrypoint with a trusting period.
+    struct ExpiringEntrypoint;
+
+    const TRUSTING_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+    impl Entrypoint for ExpiringEntrypoint {
+        type LightClient = MockLightClient;
+        type CustomQueryMsg = NoCustomQueryMsg;
+
+        fn get_status_from_client_state(any_client_state: Any) -> Result<String, ContractError> {
+            MockEntrypoint::get_status_from_client_state(any_client_state)
+        }
+
+        fn get_timestamp_from_consensus_state(
+            any_consensus_state: Any,
+        ) -> Result<u64, ContractError> {
+            MockEntrypoint::get_timestamp_from_consensus_state(any_consensus_state)
+        }
+
+        fn get_trusting_period_from_client_state(
+            _any_client_state: Any,
+        ) -> Result<Option<Duration>, ContractError> {
+            Ok(Some(TRUSTING_PERIOD))
+        }
+    }
+
+    fn client_health<E: Entrypoint<LightClient = MockLightClient>>(
+        deps: &MockDeps,
+        height: u64,
+    ) -> ClientHealthResponse {
+        let res = E::query(
+            &MockLightClient,
+            deps.as_ref(),
+            env(MockFixtures::block(height)),
+            ContractQueryMsg::Wasm(QueryMsg::ClientHealth(ClientHealthMsg {})),
+        )
+        .unwrap();
+        from_json(res).unwrap()
+    }
+
+    #[test]
+    fn test_client_health() {
+        let mut deps = instantiate();
+        let height = INITIAL_HEIGHT + 1;
+        update(&mut deps, height).unwrap();
+
+        let latest_timestamp = MockFixtures::block(height).time.nanos();
+        let host_timestamp = MockFixtures::block(height + 10).time.nanos();
+        let trusting_period = TRUSTING_PERIOD.as_nanos() as u64;
+        assert_eq!(
+            client_health::<ExpiringEntrypoint>(&deps, height + 10),
+            ClientHealthResponse {
+                latest_height: LcpHeight::new(0, height).into(),
+                latest_timestamp,
+                host_timestamp,
+                time_since_last_update: Some(host_timestamp - latest_timestamp),
+                trusting_period: Some(trusting_period),
+                expiry_timestamp: Some(latest_timestamp + trusting_period),
+            }
+        );
+
+        // a client without a trusting period never expires
+        let res = client_health::<MockEntrypoint>(&deps, height + 10);
+        assert_eq!(res.latest_timestamp, latest_timestamp);
+        assert_eq!(res.trusting_period, None);
+        assert_eq!(res.expiry_timestamp, None);
+
+        // a consensus state without update metadata has no processed time
+        deps.storage
+            .remove(&StoreKey::ProcessedTime(LcpHeight::new(0, height)).to_vec());
+        let res = client_health::<ExpiringEntrypoint>(&deps, height + 10);
+        assert_eq!(res.time_since_last_update, None);
+        assert_eq!(
+            res.expiry_timestamp,
+            Some(latest_timestamp + trusting_period)
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{HexBinary, StdError};
 use ibc::core::ics02_client::error::ClientError;
 use light_client::types::proto::protobuf::Error as ProtoError;
 use light_client::types::{ClientId, Height};
@@ -18,6 +18,7 @@ pub enum ContractError {
     FromUtf8(FromUtf8Error),
     Client(ClientError),
     TryFromInt(TryFromIntError),
+    StoreKeyNotFound(&'static str, Vec<u8>),
     Unsupported(String),
     Generic(String),
 }
@@ -30,6 +31,10 @@ impl ContractError {
     pub fn unsupported(msg: impl ToString) -> Self {
         Self::Unsupported(msg.to_string())
     }
+
+    pub fn store_key_not_found(kind: &'static str, key: Vec<u8>) -> Self {
+        Self::StoreKeyNotFound(kind, key)
+    }
 }
 
 impl Display for ContractError {
@@ -43,6 +48,12 @@ impl Display for ContractError {
             Self::FromUtf8(e) => write!(f, "ContractError::FromUtf8({})", e),
             Self::Client(e) => write!(f, "ContractError::Client({})", e),
             Self::TryFromInt(e) => write!(f, "ContractError::TryFromInt({})", e),
+            Self::StoreKeyNotFound(kind, key) => write!(
+                f,
+                "ContractError::StoreKeyNotFound(kind={}, key=0x{})",
+                kind,
+                HexBinary::from(key.as_slice()).to_hex(),
+            ),
             Self::Unsupported(e) => write!(f, "ContractError::Unsupported({})", e),
             Self::Generic(e) => write!(f, "ContractError::Generic({})", e),
         }
@@ -0,0 +1,258 @@
+use light_client::types::Height;
+
+pub const SUBJECT_PREFIX: &[u8] = b"subject/";
+pub const SUBSTITUTE_PREFIX: &[u8] = b"substitute/";
+
+pub const CLIENT_PREFIX: &str = "clients";
+pub const CLIENT_STATE: &str = "clientState";
+pub const CONSENSUS_STATE_PREFIX: &str = "consensusStates";
+pub const PROCESSED_TIME: &str = "processedTime";
+pub const PROCESSED_HEIGHT: &str = "processedHeight";
+pub const ITERATE_CONSENSUS_STATE_PREFIX: &str = "iterateConsensusStates";
+pub const ELC_PREFIX: &str = "elc/";
+pub const FROZEN_HEIGHT: &str = "frozenHeight";
+pub const MISBEHAVIOUR_EVIDENCE: &str = "misbehaviourEvidence";
+pub const UPDATE_HISTORY_PREFIX: &str = "updateHistory/";
+pub const UPDATE_HISTORY_HEIGHT_PREFIX: &str = "updateHistoryHeight/";
+pub const UPDATE_HISTORY_SEQUENCE: &str = "updateHistorySequence";
+pub const UPDATE_HISTORY_COUNT: &str = "updateHistoryCount";
+pub const UPDATE_HISTORY_SIZE: &str = "updateHistorySize";
+
+/// The prefixes of the reserved keys which are not ELC keys, in ascending order.
+pub const RESERVED_PREFIXES: &[&[u8]] = &[
+    b"consensusStates/",
+    ELC_PREFIX.as_bytes(),
+    SUBJECT_PREFIX,
+    SUBSTITUTE_PREFIX,
+    UPDATE_HISTORY_PREFIX.as_bytes(),
+    UPDATE_HISTORY_HEIGHT_PREFIX.as_bytes(),
+];
+
+/// A key in the client store, relative to the migration prefix.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StoreKey {
+    ClientState,
+    ConsensusState(Height),
+    ProcessedTime(Height),
+    ProcessedHeight(Height),
+    IterateConsensusState(Height),
+    FrozenHeight,
+    MisbehaviourEvidence,
+    /// An entry of the update history, keyed by its big-endian sequence.
+    UpdateHistory(u64),
+    /// The sequence of the update history entry of a consensus height, keyed by the
+    /// big-endian height.
+    UpdateHistoryHeight(Height),
+    UpdateHistorySequence,
+    UpdateHistoryCount,
+    /// The number of entries kept in the update history, set by `MigrateMsg`.
+    UpdateHistorySize,
+    /// A key written by the ELC through `store::KVStore`, stored under `ELC_PREFIX`
+    /// so that it cannot collide with the keys read by ibc-go.
+    Elc(Vec<u8>),
+}
+
+impl StoreKey {
+    pub fn to_vec(&self) -> Vec<u8> {
+        match self {
+            Self::ClientState => CLIENT_STATE.as_bytes().to_vec(),
+            Self::ConsensusState(height) => consensus_state_path(height).into_bytes(),
+            Self::ProcessedTime(height) => {
+                format!("{}/{PROCESSED_TIME}", consensus_state_path(height)).into_bytes()
+            }
+            Self::ProcessedHeight(height) => {
+                format!("{}/{PROCESSED_HEIGHT}", consensus_state_path(height)).into_bytes()
+            }
+            Self::IterateConsensusState(height) => [
+                ITERATE_CONSENSUS_STATE_PREFIX.as_bytes(),
+                &be_height(height),
+            ]
+            .concat(),
+            Self::FrozenHeight => FROZEN_HEIGHT.as_bytes().to_vec(),
+            Self::MisbehaviourEvidence => MISBEHAVIOUR_EVIDENCE.as_bytes().to_vec(),
+            Self::UpdateHistory(sequence) => {
+                [UPDATE_HISTORY_PREFIX.as_bytes(), &sequence.to_be_bytes()].concat()
+            }
+            Self::UpdateHistoryHeight(height) => {
+                [UPDATE_HISTORY_HEIGHT_PREFIX.as_bytes(), &be_height(height)].concat()
+            }
+            Self::UpdateHistorySequence => UPDATE_HISTORY_SEQUENCE.as_bytes().to_vec(),
+            Self::UpdateHistoryCount => UPDATE_HISTORY_COUNT.as_bytes().to_vec(),
+            Self::UpdateHistorySize => UPDATE_HISTORY_SIZE.as_bytes().to_vec(),
+            Self::Elc(key) => [ELC_PREFIX.as_bytes(), key].concat(),
+        }
+    }
+
+    /// Returns a human-readable name of the key, used in error messages.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::ClientState => "clientState",
+            Self::ConsensusState(_) => "consensusState",
+            Self::ProcessedTime(_) => "processedTime",
+            Self::ProcessedHeight(_) => "processedHeight",
+            Self::IterateConsensusState(_) => "iterateConsensusState",
+            Self::FrozenHeight => "frozenHeight",
+            Self::MisbehaviourEvidence => "misbehaviourEvidence",
+            Self::UpdateHistory(_) => "updateHistory",
+            Self::UpdateHistoryHeight(_) => "updateHistoryHeight",
+            Self::UpdateHistorySequence => "updateHistorySequence",
+            Self::UpdateHistoryCount => "updateHistoryCount",
+            Self::UpdateHistorySize => "updateHistorySize",
+            Self::Elc(_) => "elc",
+        }
+    }
+}
+
+/// The prefix applied to every key of the client store.
+///
+/// ibc-go prefixes the keys of the subject and substitute client stores
+/// when calling `MigrateClientStore`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyPrefix {
+    #[default]
+    None,
+    Subject,
+    Substitute,
+}
+
+impl KeyPrefix {
+    pub fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            Self::None => b"",
+            Self::Subject => SUBJECT_PREFIX,
+            Self::Substitute => SUBSTITUTE_PREFIX,
+        }
+    }
+
+    pub fn key(&self, key: &StoreKey) -> Vec<u8> {
+        self.prefix_raw(key.to_vec())
+    }
+
+    /// Returns the `[start, end)` storage range of the keys starting with `prefix`.
+    pub fn range(&self, prefix: impl AsRef<[u8]>) -> (Vec<u8>, Vec<u8>) {
+        let start = self.prefix_raw(prefix);
+        let end = prefix_range_end(&start);
+        (start, end)
+    }
+
+    fn prefix_raw(&self, key: impl AsRef<[u8]>) -> Vec<u8> {
+        let mut prefixed_key = self.as_bytes().to_vec();
+        prefixed_key.extend_from_slice(key.as_ref());
+        prefixed_key
+    }
+}
+
+/// Formats a height as `{revision_number}-{revision_height}`, the format used by ibc-go.
+pub fn format_height(height: &Height) -> String {
+    format!("{}-{}", height.revision_number(), height.revision_height())
+}
+
+/// Parses a height formatted as `{revision_number}-{revision_height}`.
+pub fn parse_height(s: &str) -> Option<Height> {
+    let (revision_number, revision_height) = s.split_once('-')?;
+    let revision_number = revision_number.parse::<u64>().ok()?;
+    let revision_height = revision_height.parse::<u64>().ok()?;
+    Some(Height::new(revision_number, revision_height))
+}
+
+/// Encodes a height as big-endian revision number and revision height, which sorts
+/// numerically.
+pub fn be_height(height: &Height) -> [u8; 16] {
+    let mut bytes = [0; 16];
+    bytes[..8].copy_from_slice(&height.revision_number().to_be_bytes());
+    bytes[8..].copy_from_slice(&height.revision_height().to_be_bytes());
+    bytes
+}
+
+/// Parses a height encoded as big-endian revision number and revision height.
+pub fn parse_be_height(bytes: &[u8]) -> Option<Height> {
+    if bytes.len() != 16 {
+        return None;
+    }
+    let (revision_number, revision_height) = bytes.split_at(8);
+    Some(Height::new(
+        u64::from_be_bytes(revision_number.try_into().ok()?),
+        u64::from_be_bytes(revision_height.try_into().ok()?),
+    ))
+}
+
+fn consensus_state_path(height: &Height) -> String {
+    format!("{CONSENSUS_STATE_PREFIX}/{}", format_height(height))
+}
+
+/// Parses the height of a `consensusStates/{revision_number}-{revision_height}` key.
+pub fn parse_consensus_state_key(key: &[u8]) -> Option<Height> {
+    let path = key
+        .strip_prefix(CONSENSUS_STATE_PREFIX.as_bytes())?
+        .strip_prefix(b"/")?;
+    parse_height(core::str::from_utf8(path).ok()?)
+}
+
+/// Returns whether `key` belongs to the layout managed by this crate and ibc-go
+/// rather than to the ELC.
+pub fn is_reserved_key(key: &[u8]) -> bool {
+    key == CLIENT_STATE.as_bytes()
+        || key == FROZEN_HEIGHT.as_bytes()
+        || key == MISBEHAVIOUR_EVIDENCE.as_bytes()
+        || key == UPDATE_HISTORY_SEQUENCE.as_bytes()
+        || key == UPDATE_HISTORY_COUNT.as_bytes()
+        || key == UPDATE_HISTORY_SIZE.as_bytes()
+        || key
+            .strip_prefix(ITERATE_CONSENSUS_STATE_PREFIX.as_bytes())
+            .is_some_and(|height| height.len() == 16)
+        || RESERVED_PREFIXES
+            .iter()
+            .any(|prefix| key.starts_with(prefix))
+}
+
+/// Returns the exclusive upper bound of the range of keys starting with `prefix`.
+pub fn prefix_range_end(prefix: &[u8]) -> Vec<u8> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            break;
+        }
+    }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_reserved_key() {
+        let height = Height::new(1, 2);
+        for key in [
+            StoreKey::ClientState,
+            StoreKey::ConsensusState(height),
+            StoreKey::ProcessedTime(height),
+            StoreKey::ProcessedHeight(height),
+            StoreKey::IterateConsensusState(height),
+            StoreKey::UpdateHistory(2),
+            StoreKey::UpdateHistoryHeight(height),
+            StoreKey::UpdateHistorySequence,
+            StoreKey::UpdateHistoryCount,
+            StoreKey::UpdateHistorySize,
+            StoreKey::Elc(b"clientState".to_vec()),
+        ] {
+            assert!(is_reserved_key(&key.to_vec()), "{key:?}");
+        }
+        assert!(is_reserved_key(b"subject/clientState"));
+        assert!(RESERVED_PREFIXES.windows(2).all(|w| w[0] < w[1]));
+
+        // ELC keys which only share a prefix with the reserved paths
+        let keys: [&[u8]; 6] = [
+            b"clientStates",
+            b"consensusStates",
+            b"consensusStatesExtra/1-2",
+            b"iterateConsensusStates",
+            b"iterateConsensusStatesExtra",
+            b"elc",
+        ];
+        for key in keys {
+            assert!(!is_reserved_key(key), "{key:?}");
+        }
+    }
+}
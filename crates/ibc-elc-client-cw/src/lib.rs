@@ -1,7 +1,11 @@
+pub mod backend;
 pub mod context;
 pub mod entrypoint;
 pub mod error;
+pub mod keys;
 pub mod msg;
 pub mod response;
+#[cfg(feature = "testkit")]
+pub mod testkit;
 pub mod wasm_client_state;
 pub mod wasm_consensus_state;
@@ -1,7 +1,17 @@
 use crate::error::ContractError;
+use core::fmt;
+use core::marker::PhantomData;
+use cosmwasm_schema::schemars::schema::RootSchema;
+use cosmwasm_schema::schemars::JsonSchema;
+use cosmwasm_schema::serde::de::value::MapAccessDeserializer;
+use cosmwasm_schema::serde::de::{
+    self, DeserializeSeed, IgnoredAny, IntoDeserializer, MapAccess, Visitor,
+};
+use cosmwasm_schema::serde::{Deserialize, Deserializer, Serialize, Serializer};
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::Binary;
 use light_client::types::Height as LcpHeight;
+use std::collections::BTreeMap;
 
 // ------------------------------------------------------------
 // Implementation of the InstantiateMsg struct
@@ -14,6 +24,16 @@ pub struct InstantiateMsg {
     pub checksum: Binary,
 }
 
+// ------------------------------------------------------------
+// Implementation of the MigrateMsg struct
+// ------------------------------------------------------------
+
+#[cw_serde]
+pub struct MigrateMsg {
+    /// The number of accepted headers kept in the update history, if it changes.
+    pub update_history_size: Option<u64>,
+}
+
 // ------------------------------------------------------------
 // Implementation of the SudoMsg enum and its variants
 // ------------------------------------------------------------
@@ -78,6 +98,15 @@ impl From<Height> for LcpHeight {
     }
 }
 
+impl From<LcpHeight> for Height {
+    fn from(v: LcpHeight) -> Self {
+        Self {
+            revision_number: v.revision_number(),
+            revision_height: v.revision_height(),
+        }
+    }
+}
+
 #[cw_serde]
 pub struct VerifyMembershipMsg {
     pub height: Height,
@@ -104,6 +133,116 @@ pub struct MigrateClientStoreMsg {}
 // Implementation of the QueryMsg enum and its variants
 // ------------------------------------------------------------
 
+/// The key under which the client-specific queries are sent, e.g. `{"custom":{"epoch":{}}}`.
+pub const CUSTOM_QUERY_KEY: &str = "custom";
+
+/// The query message of a contract: the 08-wasm queries, extended with the
+/// client-specific queries of `Entrypoint::CustomQueryMsg` under `CUSTOM_QUERY_KEY`.
+///
+/// Any other key is decoded as a `QueryMsg`, so that a malformed 08-wasm query
+/// fails with the decode error of `QueryMsg`.
+#[derive(Clone, Debug, PartialEq, JsonSchema)]
+#[schemars(crate = "cosmwasm_schema::schemars", untagged)]
+pub enum ContractQueryMsg<Q> {
+    Wasm(QueryMsg),
+    Custom(#[schemars(with = "CustomQuery<Q>")] Q),
+}
+
+/// The JSON layout of `ContractQueryMsg::Custom`.
+#[cw_serde]
+pub struct CustomQuery<Q> {
+    pub custom: Q,
+}
+
+impl<Q: Serialize> Serialize for ContractQueryMsg<Q> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Wasm(msg) => msg.serialize(serializer),
+            Self::Custom(custom) => CustomQuery { custom }.serialize(serializer),
+        }
+    }
+}
+
+impl<'de, Q: Deserialize<'de>> Deserialize<'de> for ContractQueryMsg<Q> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(ContractQueryMsgVisitor(PhantomData))
+    }
+}
+
+struct ContractQueryMsgVisitor<Q>(PhantomData<Q>);
+
+impl<'de, Q: Deserialize<'de>> Visitor<'de> for ContractQueryMsgVisitor<Q> {
+    type Value = ContractQueryMsg<Q>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a query message with a single key")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let key: String = map
+            .next_key()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let msg = if key == CUSTOM_QUERY_KEY {
+            ContractQueryMsg::Custom(map.next_value()?)
+        } else {
+            let entry = ReadKeyMapAccess {
+                key: Some(key),
+                map: &mut map,
+            };
+            ContractQueryMsg::Wasm(QueryMsg::deserialize(MapAccessDeserializer::new(entry))?)
+        };
+        if map.next_key::<IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(2, &self));
+        }
+        Ok(msg)
+    }
+}
+
+/// A `MapAccess` over `map` whose first key has already been read.
+struct ReadKeyMapAccess<A> {
+    key: Option<String>,
+    map: A,
+}
+
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for ReadKeyMapAccess<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.key.take() {
+            Some(key) => seed.deserialize(key.into_deserializer()).map(Some),
+            None => self.map.next_key_seed(seed),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.map.next_value_seed(seed)
+    }
+}
+
+impl<Q: QueryResponses> QueryResponses for ContractQueryMsg<Q> {
+    fn response_schemas_impl() -> BTreeMap<String, RootSchema> {
+        let mut schemas = QueryMsg::response_schemas_impl();
+        schemas.extend(Q::response_schemas_impl());
+        schemas
+    }
+}
+
+/// A custom query message for clients without client-specific queries.
+#[cw_serde]
+pub enum NoCustomQueryMsg {}
+
+impl QueryResponses for NoCustomQueryMsg {
+    fn response_schemas_impl() -> BTreeMap<String, RootSchema> {
+        BTreeMap::new()
+    }
+}
+
 #[derive(QueryResponses)]
 #[cw_serde]
 pub enum QueryMsg {
@@ -117,6 +256,22 @@ pub enum QueryMsg {
     VerifyClientMessage(VerifyClientMessageMsg),
     #[returns(crate::response::CheckForMisbehaviourResponse)]
     CheckForMisbehaviour(CheckForMisbehaviourMsg),
+    #[returns(crate::response::UpdateMetaResponse)]
+    UpdateMeta(UpdateMetaMsg),
+    #[returns(crate::response::ConsensusStateHeightsResponse)]
+    ConsensusStateHeights(ConsensusStateHeightsMsg),
+    #[returns(crate::response::ClientStateResponse)]
+    ClientState(ClientStateMsg),
+    #[returns(crate::response::ConsensusStateResponse)]
+    ConsensusState(ConsensusStateMsg),
+    #[returns(crate::response::MisbehaviourEvidenceResponse)]
+    MisbehaviourEvidence(MisbehaviourEvidenceMsg),
+    #[returns(crate::response::SimulateUpdateStateResponse)]
+    SimulateUpdateState(SimulateUpdateStateMsg),
+    #[returns(crate::response::ClientHealthResponse)]
+    ClientHealth(ClientHealthMsg),
+    #[returns(crate::response::UpdateHistoryResponse)]
+    UpdateHistory(UpdateHistoryMsg),
 }
 
 #[cw_serde]
@@ -139,3 +294,73 @@ pub struct VerifyClientMessageMsg {
 pub struct CheckForMisbehaviourMsg {
     pub client_message: Binary,
 }
+
+#[cw_serde]
+pub struct UpdateMetaMsg {
+    pub height: Height,
+}
+
+#[cw_serde]
+pub struct ConsensusStateHeightsMsg {
+    pub start_after: Option<Height>,
+    pub limit: Option<u32>,
+}
+
+#[cw_serde]
+pub struct ClientStateMsg {}
+
+#[cw_serde]
+pub struct ConsensusStateMsg {
+    pub height: Height,
+}
+
+#[cw_serde]
+pub struct MisbehaviourEvidenceMsg {}
+
+#[cw_serde]
+pub struct SimulateUpdateStateMsg {
+    pub client_message: Binary,
+}
+
+#[cw_serde]
+pub struct ClientHealthMsg {}
+
+#[cw_serde]
+pub struct UpdateHistoryMsg {
+    /// The sequence of the last entry of the previous page.
+    pub start_after: Option<u64>,
+    pub limit: Option<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{from_json, to_json_vec};
+
+    #[cw_serde]
+    enum TestQueryMsg {
+        Epoch {},
+    }
+
+    #[test]
+    fn test_contract_query_msg() {
+        let msg: ContractQueryMsg<TestQueryMsg> = from_json(br#"{"status":{}}"#).unwrap();
+        assert_eq!(msg, ContractQueryMsg::Wasm(QueryMsg::Status(StatusMsg {})));
+        assert_eq!(to_json_vec(&msg).unwrap(), br#"{"status":{}}"#);
+
+        let msg: ContractQueryMsg<TestQueryMsg> = from_json(br#"{"custom":{"epoch":{}}}"#).unwrap();
+        assert_eq!(msg, ContractQueryMsg::Custom(TestQueryMsg::Epoch {}));
+        assert_eq!(to_json_vec(&msg).unwrap(), br#"{"custom":{"epoch":{}}}"#);
+
+        // the errors of QueryMsg are not hidden by the fallback to the custom query
+        let err = from_json::<ContractQueryMsg<TestQueryMsg>>(br#"{"epoch":{}}"#).unwrap_err();
+        assert!(err.to_string().contains("unknown variant `epoch`"), "{err}");
+        let err = from_json::<ContractQueryMsg<TestQueryMsg>>(br#"{"timestamp_at_height":{}}"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("missing field `height`"), "{err}");
+
+        for msg in [&br#"{}"#[..], br#"{"status":{},"custom":{"epoch":{}}}"#] {
+            assert!(from_json::<ContractQueryMsg<TestQueryMsg>>(msg).is_err());
+        }
+    }
+}
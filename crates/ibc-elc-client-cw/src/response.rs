@@ -4,8 +4,23 @@ use light_client::types::Height as LcpHeight;
 
 #[cw_serde]
 pub struct Height {
-    revision_number: u64,
-    revision_height: u64,
+    pub revision_number: u64,
+    pub revision_height: u64,
+}
+
+impl From<Height> for LcpHeight {
+    fn from(v: Height) -> Self {
+        Self::new(v.revision_number, v.revision_height)
+    }
+}
+
+impl From<LcpHeight> for Height {
+    fn from(v: LcpHeight) -> Self {
+        Self {
+            revision_number: v.revision_number(),
+            revision_height: v.revision_height(),
+        }
+    }
 }
 
 #[cw_serde]
@@ -37,6 +52,91 @@ pub struct CheckForMisbehaviourResponse {
     pub found_misbehaviour: bool,
 }
 
+#[cw_serde]
+pub struct UpdateMetaResponse {
+    pub processed_time: u64,
+    pub processed_height: Height,
+}
+
+#[cw_serde]
+pub struct ConsensusStateHeightsResponse {
+    pub heights: Vec<Height>,
+}
+
+#[cw_serde]
+pub struct ClientStateResponse {
+    pub type_url: String,
+    pub value: Binary,
+    pub json: Option<String>,
+}
+
+#[cw_serde]
+pub struct ConsensusStateResponse {
+    pub type_url: String,
+    pub value: Binary,
+    pub json: Option<String>,
+}
+
+/// The client message that froze the client, along with the host height and time
+/// at which it was submitted and the height at which the client was frozen.
+#[cw_serde]
+pub struct MisbehaviourEvidence {
+    pub client_message: Binary,
+    pub host_height: Height,
+    pub host_timestamp: u64,
+    pub frozen_height: Height,
+}
+
+#[cw_serde]
+pub struct MisbehaviourEvidenceResponse {
+    pub evidence: Option<MisbehaviourEvidence>,
+}
+
+/// A header accepted by `UpdateState`, along with the host height and time at which
+/// it was accepted.
+#[cw_serde]
+pub struct UpdateHistoryEntry {
+    /// The order in which the header was accepted, counted from the first update.
+    pub sequence: u64,
+    pub height: Height,
+    pub host_height: Height,
+    pub host_timestamp: u64,
+    /// The type URL of the client message.
+    pub message_type: String,
+}
+
+#[cw_serde]
+pub struct UpdateHistoryResponse {
+    pub entries: Vec<UpdateHistoryEntry>,
+}
+
+/// The outcome of an `UpdateState` with the client message, computed without writing.
+///
+/// When a misbehaviour is found, `UpdateState` would fail and the other fields are unset.
+#[cw_serde]
+pub struct SimulateUpdateStateResponse {
+    pub heights: Vec<Height>,
+    pub latest_height: Option<Height>,
+    pub consensus_state_timestamp: Option<u64>,
+    pub found_misbehaviour: bool,
+}
+
+/// The freshness of the client. All times are unix timestamps and durations in
+/// nanoseconds.
+///
+/// The client expires at `expiry_timestamp`, once the trusting period has elapsed since
+/// the timestamp of the latest consensus state. `time_since_last_update` is measured
+/// from the host time at which the latest consensus state was stored.
+#[cw_serde]
+pub struct ClientHealthResponse {
+    pub latest_height: Height,
+    pub latest_timestamp: u64,
+    pub host_timestamp: u64,
+    pub time_since_last_update: Option<u64>,
+    pub trusting_period: Option<u64>,
+    pub expiry_timestamp: Option<u64>,
+}
+
 #[cw_serde]
 pub struct ContractResult {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -49,13 +149,7 @@ impl ContractResult {
     }
 
     pub fn heights(mut self, heights: Vec<LcpHeight>) -> Self {
-        let heights = heights
-            .into_iter()
-            .map(|h| Height {
-                revision_number: h.revision_number(),
-                revision_height: h.revision_height(),
-            })
-            .collect();
+        let heights = heights.into_iter().map(Height::from).collect();
         self.heights = Some(heights);
         self
     }
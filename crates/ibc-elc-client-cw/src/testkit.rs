@@ -0,0 +1,503 @@
+//! A conformance test kit for `Entrypoint` implementations.
+//!
+//! Given the fixtures of a client, `run_all` checks that the contract follows the
+//! 08-wasm contract semantics expected by ibc-go: the client store layout, the update
+//! metadata, misbehaviour freezing, status and timestamp queries, membership
+//! verification, and that malformed inputs are rejected with errors instead of panics.
+
+pub mod keeper;
+pub mod mock;
+
+use crate::entrypoint::Entrypoint;
+use crate::error::ContractError;
+use crate::keys::StoreKey;
+use crate::msg::{self, *};
+use crate::response::*;
+use crate::wasm_client_state::WasmClientState;
+use crate::wasm_consensus_state::WasmConsensusState;
+use cosmwasm_schema::serde::de::DeserializeOwned;
+use cosmwasm_std::testing::{
+    message_info, mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage,
+};
+use cosmwasm_std::{
+    from_json, Addr, Binary, BlockInfo, Env, Order, OwnedDeps, Record, Response, Storage,
+};
+use light_client::types::{Any, Height as LcpHeight};
+use prost::Message;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+pub const CLIENT_ID: &str = "08-wasm-0";
+
+const ACTIVE: &str = "Active";
+const FROZEN: &str = "Frozen";
+
+type LightClientOf<F> = <<F as Fixtures>::Entrypoint as Entrypoint>::LightClient;
+
+/// The fixtures of a client, generated by the ELC implementor.
+pub trait Fixtures {
+    type Entrypoint: Entrypoint;
+
+    fn light_client(&self) -> LightClientOf<Self>;
+
+    /// Returns the message instantiating the client and the host block at which it is sent.
+    fn instantiate(&self) -> (InstantiateMsg, BlockInfo);
+
+    /// Returns a valid header for the instantiated client.
+    fn update(&self) -> (UpdateStateMsg, BlockInfo);
+
+    /// Returns a misbehaviour for the client updated by `update`, if supported.
+    fn misbehaviour(&self) -> Option<(UpdateStateOnMisbehaviourMsg, BlockInfo)> {
+        None
+    }
+
+    /// Returns a membership proof verifiable after `update`, if supported.
+    fn membership(&self) -> Option<(VerifyMembershipMsg, BlockInfo)> {
+        None
+    }
+
+    /// Returns a non-membership proof verifiable after `update`, if supported.
+    fn non_membership(&self) -> Option<(VerifyNonMembershipMsg, BlockInfo)> {
+        None
+    }
+}
+
+/// Runs all the checks of the test kit, panicking on the first failure.
+pub fn run_all<F: Fixtures>(fixtures: &F) {
+    check_instantiate_layout(fixtures);
+    check_update_state(fixtures);
+    check_status_and_timestamp(fixtures);
+    check_misbehaviour(fixtures);
+    check_membership(fixtures);
+    check_errors_not_panics(fixtures);
+}
+
+/// Returns the `Env` passed by ibc-go to the contract of `CLIENT_ID` at `block`.
+pub fn env(block: BlockInfo) -> Env {
+    let mut env = mock_env();
+    env.block = block;
+    env.contract.address = Addr::unchecked(CLIENT_ID);
+    env
+}
+
+/// Checks that instantiation writes the client and consensus states, the update
+/// metadata and the height index as ibc-go expects them.
+pub fn check_instantiate_layout<F: Fixtures>(fixtures: &F) {
+    let mut harness = Harness::new(fixtures);
+    let (msg, block) = harness.instantiate();
+
+    let wasm_client_state = harness.wasm_client_state();
+    assert_eq!(wasm_client_state.checksum, msg.checksum.to_vec());
+    assert_eq!(wasm_client_state.data, msg.client_state.to_vec());
+    let height = latest_height(&wasm_client_state);
+
+    let wasm_consensus_state = harness.wasm_consensus_state(height);
+    assert_eq!(wasm_consensus_state.data, msg.consensus_state.to_vec());
+
+    harness.assert_update_meta(height, &block);
+    assert_eq!(
+        harness.get(&StoreKey::IterateConsensusState(height)),
+        Some(StoreKey::ConsensusState(height).to_vec()),
+    );
+}
+
+/// Checks that `UpdateState` stores the new states and update metadata, and that
+/// resubmitting the same header is a no-op.
+pub fn check_update_state<F: Fixtures>(fixtures: &F) {
+    let mut harness = Harness::new(fixtures);
+    harness.instantiate();
+
+    let (msg, block) = fixtures.update();
+    let heights = harness.update_state(msg.clone(), block.clone());
+    assert!(
+        !heights.is_empty(),
+        "UpdateState must return the updated heights"
+    );
+
+    for height in heights.iter().copied() {
+        harness.wasm_consensus_state(height);
+        harness.assert_update_meta(height, &block);
+
+        let res: UpdateMetaResponse = harness
+            .query(
+                block.clone(),
+                QueryMsg::UpdateMeta(UpdateMetaMsg {
+                    height: height.into(),
+                }),
+            )
+            .expect("UpdateMeta failed");
+        assert_eq!(res.processed_time, block.time.nanos());
+    }
+    let max_height = heights
+        .iter()
+        .copied()
+        .max_by_key(|h| (h.revision_number(), h.revision_height()))
+        .unwrap();
+    assert_eq!(latest_height(&harness.wasm_client_state()), max_height);
+
+    let records = harness.records();
+    harness.update_state(msg, block);
+    assert!(
+        records == harness.records(),
+        "resubmitting a header must not modify the client store",
+    );
+}
+
+/// Checks the `Status` of an active client and `TimestampAtHeight`.
+pub fn check_status_and_timestamp<F: Fixtures>(fixtures: &F) {
+    let mut harness = Harness::new(fixtures);
+    let (msg, block) = harness.instantiate();
+
+    let res: StatusResponse = harness
+        .query(block.clone(), QueryMsg::Status(StatusMsg {}))
+        .expect("Status failed");
+    assert_eq!(res.status, ACTIVE);
+
+    let height = latest_height(&harness.wasm_client_state());
+    let any_consensus_state = Any::decode(msg.consensus_state.as_slice()).unwrap();
+    let expected = F::Entrypoint::get_timestamp_from_consensus_state(any_consensus_state)
+        .expect("failed to get the timestamp of the consensus state");
+    let res: TimestampAtHeightResponse = harness
+        .query(
+            block.clone(),
+            QueryMsg::TimestampAtHeight(TimestampAtHeightMsg {
+                height: height.into(),
+            }),
+        )
+        .expect("TimestampAtHeight failed");
+    assert_eq!(res.timestamp, expected);
+
+    let res = harness.query::<TimestampAtHeightResponse>(
+        block,
+        QueryMsg::TimestampAtHeight(TimestampAtHeightMsg {
+            height: LcpHeight::new(u64::MAX, u64::MAX).into(),
+        }),
+    );
+    assert!(
+        res.is_err(),
+        "TimestampAtHeight must fail for unknown heights"
+    );
+}
+
+/// Checks that a misbehaviour is detected and freezes the client.
+pub fn check_misbehaviour<F: Fixtures>(fixtures: &F) {
+    let Some((misbehaviour, block)) = fixtures.misbehaviour() else {
+        return;
+    };
+    let mut harness = Harness::new(fixtures);
+    harness.instantiate();
+    let (update, update_block) = fixtures.update();
+    harness.update_state(update.clone(), update_block);
+
+    let res: CheckForMisbehaviourResponse = harness
+        .query(
+            block.clone(),
+            QueryMsg::CheckForMisbehaviour(CheckForMisbehaviourMsg {
+                client_message: update.client_message,
+            }),
+        )
+        .expect("CheckForMisbehaviour failed for a valid header");
+    assert!(
+        !res.found_misbehaviour,
+        "a valid header is not a misbehaviour"
+    );
+
+    let res: CheckForMisbehaviourResponse = harness
+        .query(
+            block.clone(),
+            QueryMsg::CheckForMisbehaviour(CheckForMisbehaviourMsg {
+                client_message: misbehaviour.client_message.clone(),
+            }),
+        )
+        .expect("CheckForMisbehaviour failed for a misbehaviour");
+    assert!(res.found_misbehaviour, "the misbehaviour must be detected");
+
+    harness
+        .sudo(
+            block.clone(),
+            SudoMsg::UpdateStateOnMisbehaviour(misbehaviour.clone()),
+        )
+        .expect("UpdateStateOnMisbehaviour failed");
+
+    let res: StatusResponse = harness
+        .query(block.clone(), QueryMsg::Status(StatusMsg {}))
+        .expect("Status failed");
+    assert_eq!(res.status, FROZEN);
+
+    let res: MisbehaviourEvidenceResponse = harness
+        .query(
+            block,
+            QueryMsg::MisbehaviourEvidence(MisbehaviourEvidenceMsg {}),
+        )
+        .expect("MisbehaviourEvidence failed");
+    let evidence = res
+        .evidence
+        .expect("the misbehaviour evidence must be stored");
+    assert_eq!(evidence.client_message, misbehaviour.client_message);
+}
+
+/// Checks membership and non-membership verification, including a tampered value.
+pub fn check_membership<F: Fixtures>(fixtures: &F) {
+    let mut harness = Harness::new(fixtures);
+    harness.instantiate();
+    let (update, update_block) = fixtures.update();
+    harness.update_state(update, update_block);
+
+    if let Some((msg, block)) = fixtures.membership() {
+        harness
+            .sudo(block.clone(), SudoMsg::VerifyMembership(msg.clone()))
+            .expect("VerifyMembership failed");
+
+        let mut tampered = msg;
+        let mut value = tampered.value.to_vec();
+        value.push(0);
+        tampered.value = value.into();
+        harness.assert_error(block, SudoMsg::VerifyMembership(tampered));
+    }
+    if let Some((msg, block)) = fixtures.non_membership() {
+        harness
+            .sudo(block, SudoMsg::VerifyNonMembership(msg))
+            .expect("VerifyNonMembership failed");
+    }
+}
+
+/// Checks that malformed or unexpected messages are rejected with errors instead of
+/// panics, and without modifying the client store.
+pub fn check_errors_not_panics<F: Fixtures>(fixtures: &F) {
+    let mut harness = Harness::new(fixtures);
+    harness.instantiate();
+    let (update, block) = fixtures.update();
+
+    let garbage = Binary::from(b"garbage".to_vec());
+    let unknown_any = Binary::from(Any::new("/unknown".to_owned(), vec![]).encode_to_vec());
+    for client_message in [garbage.clone(), unknown_any] {
+        harness.assert_error(
+            block.clone(),
+            SudoMsg::UpdateState(UpdateStateMsg {
+                client_message: client_message.clone(),
+            }),
+        );
+        harness.assert_error(
+            block.clone(),
+            SudoMsg::UpdateStateOnMisbehaviour(UpdateStateOnMisbehaviourMsg {
+                client_message: client_message.clone(),
+            }),
+        );
+        harness.assert_query_error(
+            block.clone(),
+            QueryMsg::VerifyClientMessage(VerifyClientMessageMsg {
+                client_message: client_message.clone(),
+            }),
+        );
+    }
+    harness.assert_query_error(
+        block.clone(),
+        QueryMsg::CheckForMisbehaviour(CheckForMisbehaviourMsg {
+            client_message: garbage.clone(),
+        }),
+    );
+
+    // a valid header is not a misbehaviour
+    harness.assert_error(
+        block.clone(),
+        SudoMsg::UpdateStateOnMisbehaviour(UpdateStateOnMisbehaviourMsg {
+            client_message: update.client_message,
+        }),
+    );
+    // a misbehaviour is not a header
+    if let Some((misbehaviour, _)) = fixtures.misbehaviour() {
+        harness.assert_error(
+            block.clone(),
+            SudoMsg::UpdateState(UpdateStateMsg {
+                client_message: misbehaviour.client_message,
+            }),
+        );
+    }
+
+    let height = latest_height(&harness.wasm_client_state()).into();
+    let merkle_path = MerklePath {
+        key_path: vec![b"ibc".to_vec().into(), b"path".to_vec().into()],
+    };
+    harness.assert_error(
+        block.clone(),
+        SudoMsg::VerifyMembership(VerifyMembershipMsg {
+            height,
+            delay_time_period: 0,
+            delay_block_period: 0,
+            proof: garbage.clone(),
+            merkle_path: merkle_path.clone(),
+            value: garbage.clone(),
+        }),
+    );
+    harness.assert_error(
+        block.clone(),
+        SudoMsg::VerifyNonMembership(VerifyNonMembershipMsg {
+            height: LcpHeight::new(u64::MAX, u64::MAX).into(),
+            delay_time_period: 0,
+            delay_block_period: 0,
+            proof: garbage,
+            merkle_path,
+        }),
+    );
+    harness.assert_error(
+        block,
+        SudoMsg::VerifyMembership(VerifyMembershipMsg {
+            height: msg::Height {
+                revision_number: 0,
+                revision_height: 0,
+            },
+            delay_time_period: 0,
+            delay_block_period: 0,
+            proof: Binary::default(),
+            merkle_path: MerklePath { key_path: vec![] },
+            value: Binary::default(),
+        }),
+    );
+}
+
+/// Checks that the state hooks of `E` reject the states of other client types.
+pub fn check_foreign_states_are_errors<E: Entrypoint>() {
+    let any = Any::new("/unknown".to_owned(), vec![1, 2, 3]);
+    assert!(E::get_status_from_client_state(any.clone()).is_err());
+    assert!(E::get_trusting_period_from_client_state(any.clone()).is_err());
+    assert!(E::get_timestamp_from_consensus_state(any).is_err());
+}
+
+fn latest_height(wasm_client_state: &WasmClientState) -> LcpHeight {
+    let height = wasm_client_state
+        .latest_height
+        .as_ref()
+        .expect("the latest height must be set in the wasm client state");
+    LcpHeight::new(height.revision_number, height.revision_height)
+}
+
+struct Harness<'a, F: Fixtures> {
+    fixtures: &'a F,
+    lc: LightClientOf<F>,
+    deps: OwnedDeps<MockStorage, MockApi, MockQuerier>,
+}
+
+impl<'a, F: Fixtures> Harness<'a, F> {
+    fn new(fixtures: &'a F) -> Self {
+        Self {
+            fixtures,
+            lc: fixtures.light_client(),
+            deps: mock_dependencies(),
+        }
+    }
+
+    fn instantiate(&mut self) -> (InstantiateMsg, BlockInfo) {
+        let (msg, block) = self.fixtures.instantiate();
+        let info = message_info(&Addr::unchecked("sender"), &[]);
+        <F::Entrypoint as Entrypoint>::instantiate(
+            &self.lc,
+            self.deps.as_mut(),
+            env(block.clone()),
+            info,
+            msg.clone(),
+        )
+        .expect("instantiate failed");
+        (msg, block)
+    }
+
+    fn sudo(&mut self, block: BlockInfo, msg: SudoMsg) -> Result<Response, ContractError> {
+        <F::Entrypoint as Entrypoint>::sudo(&self.lc, self.deps.as_mut(), env(block), msg)
+    }
+
+    fn query<T: DeserializeOwned>(
+        &self,
+        block: BlockInfo,
+        msg: QueryMsg,
+    ) -> Result<T, ContractError> {
+        Ok(from_json(self.query_raw(block, msg)?)?)
+    }
+
+    fn query_raw(&self, block: BlockInfo, msg: QueryMsg) -> Result<Binary, ContractError> {
+        <F::Entrypoint as Entrypoint>::query(
+            &self.lc,
+            self.deps.as_ref(),
+            env(block),
+            ContractQueryMsg::Wasm(msg),
+        )
+    }
+
+    fn update_state(&mut self, msg: UpdateStateMsg, block: BlockInfo) -> Vec<LcpHeight> {
+        let res = self
+            .sudo(block, SudoMsg::UpdateState(msg))
+            .expect("UpdateState failed");
+        let data = res.data.expect("UpdateState must return a ContractResult");
+        let result: ContractResult = from_json(data).expect("invalid ContractResult");
+        result
+            .heights
+            .unwrap_or_default()
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+
+    fn get(&self, key: &StoreKey) -> Option<Vec<u8>> {
+        self.deps.storage.get(&key.to_vec())
+    }
+
+    fn records(&self) -> Vec<Record> {
+        self.deps
+            .storage
+            .range(None, None, Order::Ascending)
+            .collect()
+    }
+
+    fn wasm_client_state(&self) -> WasmClientState {
+        let value = self
+            .get(&StoreKey::ClientState)
+            .expect("the client state must be stored");
+        Any::decode(value.as_slice())
+            .expect("the client state must be an Any")
+            .try_into()
+            .expect("the client state must be a wasm client state")
+    }
+
+    fn wasm_consensus_state(&self, height: LcpHeight) -> WasmConsensusState {
+        let value = self
+            .get(&StoreKey::ConsensusState(height))
+            .expect("the consensus state must be stored");
+        Any::decode(value.as_slice())
+            .expect("the consensus state must be an Any")
+            .try_into()
+            .expect("the consensus state must be a wasm consensus state")
+    }
+
+    fn assert_update_meta(&self, height: LcpHeight, block: &BlockInfo) {
+        assert_eq!(
+            self.get(&StoreKey::ProcessedTime(height)),
+            Some(block.time.nanos().to_be_bytes().to_vec()),
+        );
+        let processed_height = self
+            .get(&StoreKey::ProcessedHeight(height))
+            .expect("the processed height must be stored");
+        let processed_height = String::from_utf8(processed_height).unwrap();
+        assert!(
+            processed_height.ends_with(&format!("-{}", block.height)),
+            "unexpected processed height: {processed_height}",
+        );
+    }
+
+    fn assert_error(&mut self, block: BlockInfo, msg: SudoMsg) {
+        let records = self.records();
+        let res = catch_unwind(AssertUnwindSafe(|| self.sudo(block, msg.clone())));
+        match res {
+            Ok(res) => assert!(res.is_err(), "{msg:?} must fail"),
+            Err(_) => panic!("{msg:?} must not panic"),
+        }
+        assert!(
+            records == self.records(),
+            "{msg:?} must not modify the client store on error",
+        );
+    }
+
+    fn assert_query_error(&self, block: BlockInfo, msg: QueryMsg) {
+        let res = catch_unwind(AssertUnwindSafe(|| self.query_raw(block, msg.clone())));
+        match res {
+            Ok(res) => assert!(res.is_err(), "{msg:?} must fail"),
+            Err(_) => panic!("{msg:?} must not panic"),
+        }
+    }
+}
@@ -0,0 +1,622 @@
+//! A stand-in for the 08-wasm light client module of ibc-go.
+//!
+//! `WasmKeeper` drives a contract the way ibc-go does: every client store lives under
+//! `clients/{client_id}/` of a shared store, `env.contract.address` is the client id,
+//! the client and consensus states are wrapped as `WasmClientState` and
+//! `WasmConsensusState`, and the contract is called in the order of the 02-client
+//! keeper. The responses are checked against what ibc-go reads back from the store.
+
+use crate::entrypoint::Entrypoint;
+use crate::error::ContractError;
+use crate::keys::{prefix_range_end, StoreKey};
+use crate::msg::*;
+use crate::response::{
+    CheckForMisbehaviourResponse, ContractResult, StatusResponse, TimestampAtHeightResponse,
+    VerifyClientMessageResponse,
+};
+use crate::wasm_client_state::WasmClientState;
+use crate::wasm_consensus_state::WasmConsensusState;
+use cosmwasm_schema::serde::de::DeserializeOwned;
+use cosmwasm_std::testing::{message_info, mock_env, MockApi, MockQuerier, MockStorage};
+use cosmwasm_std::{
+    from_json, Addr, Binary, BlockInfo, Deps, DepsMut, Env, Order, QuerierWrapper, Record, Storage,
+};
+use light_client::types::{Any, Height as LcpHeight};
+use prost::Message;
+
+pub const WASM_CLIENT_TYPE: &str = "08-wasm";
+
+const ACTIVE: &str = "Active";
+const CHECKSUM_LENGTH: usize = 32;
+
+type LightClientOf<E> = <E as Entrypoint>::LightClient;
+
+/// The outcome of `WasmKeeper::update_client`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClientUpdate {
+    /// The header was applied at the given consensus heights.
+    Updated(Vec<LcpHeight>),
+    /// The client message was a misbehaviour and the client was frozen.
+    Frozen,
+}
+
+pub struct WasmKeeper<E: Entrypoint> {
+    lc: LightClientOf<E>,
+    checksum: Vec<u8>,
+    storage: MockStorage,
+    api: MockApi,
+    querier: MockQuerier,
+    block: BlockInfo,
+    next_client_sequence: u64,
+}
+
+impl<E: Entrypoint> WasmKeeper<E> {
+    pub fn new(lc: LightClientOf<E>, checksum: Vec<u8>) -> Self {
+        Self {
+            lc,
+            checksum,
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: MockQuerier::default(),
+            block: mock_env().block,
+            next_client_sequence: 0,
+        }
+    }
+
+    pub fn block(&self) -> &BlockInfo {
+        &self.block
+    }
+
+    pub fn set_block(&mut self, block: BlockInfo) {
+        self.block = block;
+    }
+
+    /// Returns the shared store holding the client stores of every client.
+    pub fn storage(&self) -> &MockStorage {
+        &self.storage
+    }
+
+    /// Creates a client as `MsgCreateClient` does, and returns its client id.
+    ///
+    /// The contract must store a client state at `latest_height`, the height of the
+    /// `WasmClientState` submitted to ibc-go.
+    pub fn create_client(
+        &mut self,
+        any_client_state: Any,
+        any_consensus_state: Any,
+        latest_height: LcpHeight,
+    ) -> Result<String, ContractError> {
+        let wasm_client_state = WasmClientState {
+            data: any_client_state.encode_to_vec(),
+            checksum: self.checksum.clone(),
+            latest_height: Some(latest_height.into()),
+        };
+        let wasm_consensus_state = WasmConsensusState {
+            data: any_consensus_state.encode_to_vec(),
+        };
+        if wasm_client_state.data.is_empty() || wasm_consensus_state.data.is_empty() {
+            return Err(ContractError::generic("empty client or consensus state"));
+        }
+        if wasm_client_state.checksum.len() != CHECKSUM_LENGTH {
+            return Err(ContractError::generic(format!(
+                "invalid checksum length: {}",
+                wasm_client_state.checksum.len()
+            )));
+        }
+
+        let client_id = format!("{WASM_CLIENT_TYPE}-{}", self.next_client_sequence);
+        let msg = InstantiateMsg {
+            client_state: wasm_client_state.data.into(),
+            consensus_state: wasm_consensus_state.data.into(),
+            checksum: wasm_client_state.checksum.into(),
+        };
+        let snapshot = self.client_records(&client_id);
+        if let Err(e) = self.instantiate(&client_id, msg, latest_height) {
+            self.restore(&client_id, snapshot);
+            return Err(e);
+        }
+
+        self.next_client_sequence += 1;
+        Ok(client_id)
+    }
+
+    fn instantiate(
+        &mut self,
+        client_id: &str,
+        msg: InstantiateMsg,
+        latest_height: LcpHeight,
+    ) -> Result<(), ContractError> {
+        let env = self.env(client_id);
+        let info = message_info(&Addr::unchecked(client_id), &[]);
+        let mut store = ClientStore::new(&mut self.storage, client_id);
+        let deps = DepsMut {
+            storage: &mut store,
+            api: &self.api,
+            querier: QuerierWrapper::new(&self.querier),
+        };
+        E::instantiate(&self.lc, deps, env, info, msg)?;
+
+        let client_state = self.client_state(client_id)?;
+        if client_state.checksum != self.checksum {
+            return Err(ContractError::generic(
+                "the stored client state has an unexpected checksum",
+            ));
+        }
+        let stored_latest_height = self.latest_height(client_id)?;
+        if stored_latest_height != latest_height {
+            return Err(ContractError::generic(format!(
+                "unexpected latest height {stored_latest_height:?}, expected {latest_height:?}"
+            )));
+        }
+        self.consensus_state(client_id, latest_height)?;
+        self.ensure_active(client_id)
+    }
+
+    /// Updates a client as `MsgUpdateClient` does: the client message is verified,
+    /// checked for misbehaviour, and then either freezes or updates the client.
+    pub fn update_client(
+        &mut self,
+        client_id: &str,
+        client_message: Binary,
+    ) -> Result<ClientUpdate, ContractError> {
+        self.ensure_active(client_id)?;
+
+        let _: VerifyClientMessageResponse = self.query(
+            client_id,
+            QueryMsg::VerifyClientMessage(VerifyClientMessageMsg {
+                client_message: client_message.clone(),
+            }),
+        )?;
+        let res: CheckForMisbehaviourResponse = self.query(
+            client_id,
+            QueryMsg::CheckForMisbehaviour(CheckForMisbehaviourMsg {
+                client_message: client_message.clone(),
+            }),
+        )?;
+
+        if res.found_misbehaviour {
+            self.sudo(
+                client_id,
+                SudoMsg::UpdateStateOnMisbehaviour(UpdateStateOnMisbehaviourMsg { client_message }),
+            )?;
+            if self.status(client_id)? == ACTIVE {
+                return Err(ContractError::generic(
+                    "the client is still active after a misbehaviour",
+                ));
+            }
+            return Ok(ClientUpdate::Frozen);
+        }
+
+        let data = self.sudo(
+            client_id,
+            SudoMsg::UpdateState(UpdateStateMsg { client_message }),
+        )?;
+        let data = data.ok_or_else(|| ContractError::generic("UpdateState returned no data"))?;
+        let res: ContractResult = from_json(data)?;
+        let heights: Vec<LcpHeight> = res
+            .heights
+            .ok_or_else(|| ContractError::generic("UpdateState returned no heights"))?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        for height in heights.iter() {
+            self.consensus_state(client_id, *height)?;
+        }
+        Ok(ClientUpdate::Updated(heights))
+    }
+
+    /// Verifies a membership proof as the ICS-03/04 handlers do.
+    pub fn verify_membership(
+        &mut self,
+        client_id: &str,
+        height: LcpHeight,
+        proof: Binary,
+        merkle_path: MerklePath,
+        value: Binary,
+    ) -> Result<(), ContractError> {
+        self.ensure_proof_height(client_id, height)?;
+        self.sudo(
+            client_id,
+            SudoMsg::VerifyMembership(VerifyMembershipMsg {
+                height: height.into(),
+                delay_time_period: 0,
+                delay_block_period: 0,
+                proof,
+                merkle_path,
+                value,
+            }),
+        )?;
+        Ok(())
+    }
+
+    /// Verifies a non-membership proof as the ICS-03/04 handlers do.
+    pub fn verify_non_membership(
+        &mut self,
+        client_id: &str,
+        height: LcpHeight,
+        proof: Binary,
+        merkle_path: MerklePath,
+    ) -> Result<(), ContractError> {
+        self.ensure_proof_height(client_id, height)?;
+        self.sudo(
+            client_id,
+            SudoMsg::VerifyNonMembership(VerifyNonMembershipMsg {
+                height: height.into(),
+                delay_time_period: 0,
+                delay_block_period: 0,
+                proof,
+                merkle_path,
+            }),
+        )?;
+        Ok(())
+    }
+
+    pub fn status(&mut self, client_id: &str) -> Result<String, ContractError> {
+        let res: StatusResponse = self.query(client_id, QueryMsg::Status(StatusMsg {}))?;
+        Ok(res.status)
+    }
+
+    pub fn timestamp_at_height(
+        &mut self,
+        client_id: &str,
+        height: LcpHeight,
+    ) -> Result<u64, ContractError> {
+        let res: TimestampAtHeightResponse = self.query(
+            client_id,
+            QueryMsg::TimestampAtHeight(TimestampAtHeightMsg {
+                height: height.into(),
+            }),
+        )?;
+        Ok(res.timestamp)
+    }
+
+    /// Returns every record of the client store of `client_id`.
+    pub fn client_records(&self, client_id: &str) -> Vec<Record> {
+        ClientStore::range_of(&self.storage, client_id)
+    }
+
+    /// Reads the client state of a client as ibc-go does.
+    pub fn client_state(&self, client_id: &str) -> Result<WasmClientState, ContractError> {
+        let value = self
+            .get(client_id, &StoreKey::ClientState)
+            .ok_or_else(|| ContractError::generic(format!("client {client_id} not found")))?;
+        Ok(Any::decode(value.as_slice())?.try_into()?)
+    }
+
+    /// Reads the consensus state of a client at `height` as ibc-go does.
+    pub fn consensus_state(
+        &self,
+        client_id: &str,
+        height: LcpHeight,
+    ) -> Result<WasmConsensusState, ContractError> {
+        let value = self
+            .get(client_id, &StoreKey::ConsensusState(height))
+            .ok_or_else(|| {
+                ContractError::generic(format!(
+                    "consensus state of {client_id} not found at {height:?}"
+                ))
+            })?;
+        Ok(Any::decode(value.as_slice())?.try_into()?)
+    }
+
+    /// Returns the latest height of the client state, which ibc-go reads from the
+    /// stored `WasmClientState` rather than from the contract.
+    pub fn latest_height(&self, client_id: &str) -> Result<LcpHeight, ContractError> {
+        let height = self
+            .client_state(client_id)?
+            .latest_height
+            .ok_or_else(|| ContractError::generic("latest height not set"))?;
+        Ok(LcpHeight::new(
+            height.revision_number,
+            height.revision_height,
+        ))
+    }
+
+    fn ensure_active(&mut self, client_id: &str) -> Result<(), ContractError> {
+        let status = self.status(client_id)?;
+        if status != ACTIVE {
+            return Err(ContractError::generic(format!(
+                "client {client_id} is not active: {status}"
+            )));
+        }
+        Ok(())
+    }
+
+    fn ensure_proof_height(
+        &mut self,
+        client_id: &str,
+        height: LcpHeight,
+    ) -> Result<(), ContractError> {
+        self.ensure_active(client_id)?;
+        let latest_height = self.latest_height(client_id)?;
+        let latest = (
+            latest_height.revision_number(),
+            latest_height.revision_height(),
+        );
+        if latest < (height.revision_number(), height.revision_height()) {
+            return Err(ContractError::generic(format!(
+                "client state height < proof height ({latest_height:?} < {height:?})"
+            )));
+        }
+        Ok(())
+    }
+
+    fn get(&self, client_id: &str, key: &StoreKey) -> Option<Vec<u8>> {
+        self.storage
+            .get(&[client_prefix(client_id), key.to_vec()].concat())
+    }
+
+    /// Calls the contract in a cached context as ibc-go does: the writes of a failed
+    /// call are discarded.
+    fn sudo(&mut self, client_id: &str, msg: SudoMsg) -> Result<Option<Binary>, ContractError> {
+        let snapshot = self.client_records(client_id);
+        let env = self.env(client_id);
+        let mut store = ClientStore::new(&mut self.storage, client_id);
+        let deps = DepsMut {
+            storage: &mut store,
+            api: &self.api,
+            querier: QuerierWrapper::new(&self.querier),
+        };
+        match E::sudo(&self.lc, deps, env, msg) {
+            Ok(res) => Ok(res.data),
+            Err(e) => {
+                self.restore(client_id, snapshot);
+                Err(e)
+            }
+        }
+    }
+
+    /// Replaces the client store of `client_id` with `records`.
+    fn restore(&mut self, client_id: &str, records: Vec<Record>) {
+        let stale_records = self.client_records(client_id);
+        let mut store = ClientStore::new(&mut self.storage, client_id);
+        for (key, _) in stale_records {
+            store.remove(&key);
+        }
+        for (key, value) in records {
+            store.set(&key, &value);
+        }
+    }
+
+    fn query<T: DeserializeOwned>(
+        &mut self,
+        client_id: &str,
+        msg: QueryMsg,
+    ) -> Result<T, ContractError> {
+        let env = self.env(client_id);
+        let store = ClientStore::new(&mut self.storage, client_id);
+        let deps = Deps {
+            storage: &store,
+            api: &self.api,
+            querier: QuerierWrapper::new(&self.querier),
+        };
+        let res = E::query(&self.lc, deps, env, ContractQueryMsg::Wasm(msg))?;
+        Ok(from_json(res)?)
+    }
+
+    fn env(&self, client_id: &str) -> Env {
+        let mut env = mock_env();
+        env.block = self.block.clone();
+        env.contract.address = Addr::unchecked(client_id);
+        env
+    }
+}
+
+/// Returns the prefix of the client store of `client_id`.
+pub fn client_prefix(client_id: &str) -> Vec<u8> {
+    format!("clients/{client_id}/").into_bytes()
+}
+
+/// The client store of a client, a prefixed view of the shared store.
+struct ClientStore<'a> {
+    storage: &'a mut MockStorage,
+    prefix: Vec<u8>,
+}
+
+impl<'a> ClientStore<'a> {
+    fn new(storage: &'a mut MockStorage, client_id: &str) -> Self {
+        Self {
+            storage,
+            prefix: client_prefix(client_id),
+        }
+    }
+
+    fn prefixed(&self, key: &[u8]) -> Vec<u8> {
+        [self.prefix.as_slice(), key].concat()
+    }
+
+    /// Returns every record of the client store of `client_id` in `storage`.
+    fn range_of(storage: &MockStorage, client_id: &str) -> Vec<Record> {
+        let prefix = client_prefix(client_id);
+        let end = prefix_range_end(&prefix);
+        storage
+            .range(Some(&prefix), Some(&end), Order::Ascending)
+            .map(|(key, value)| (key[prefix.len()..].to_vec(), value))
+            .collect()
+    }
+}
+
+impl<'a> Storage for ClientStore<'a> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.storage.get(&self.prefixed(key))
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        let start = self.prefixed(start.unwrap_or_default());
+        let end = match end {
+            Some(end) => self.prefixed(end),
+            None => prefix_range_end(&self.prefix),
+        };
+        let prefix_len = self.prefix.len();
+        Box::new(
+            self.storage
+                .range(Some(&start), Some(&end), order)
+                .map(move |(key, value)| (key[prefix_len..].to_vec(), value)),
+        )
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        let key = self.prefixed(key);
+        self.storage.set(&key, value);
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        let key = self.prefixed(key);
+        self.storage.remove(&key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit::mock::{MockEntrypoint, MockFixtures, INITIAL_HEIGHT};
+    use crate::testkit::Fixtures;
+    use cosmwasm_std::Response;
+    use mock_lc::MockLightClient;
+
+    /// An entrypoint whose sudo calls write to the client store before failing.
+    struct FailingEntrypoint;
+
+    impl Entrypoint for FailingEntrypoint {
+        type LightClient = MockLightClient;
+        type CustomQueryMsg = NoCustomQueryMsg;
+
+        fn get_status_from_client_state(any_client_state: Any) -> Result<String, ContractError> {
+            MockEntrypoint::get_status_from_client_state(any_client_state)
+        }
+
+        fn get_timestamp_from_consensus_state(
+            any_consensus_state: Any,
+        ) -> Result<u64, ContractError> {
+            MockEntrypoint::get_timestamp_from_consensus_state(any_consensus_state)
+        }
+
+        fn sudo(
+            _lc: &Self::LightClient,
+            mut deps: DepsMut<'_>,
+            _env: Env,
+            _msg: SudoMsg,
+        ) -> Result<Response, ContractError> {
+            deps.storage.set(&StoreKey::FrozenHeight.to_vec(), b"0-1");
+            deps.storage.remove(&StoreKey::ClientState.to_vec());
+            Err(ContractError::generic("failed after writing"))
+        }
+    }
+
+    fn create_client<E: Entrypoint<LightClient = MockLightClient>>(
+        keeper: &mut WasmKeeper<E>,
+        latest_height: LcpHeight,
+    ) -> Result<String, ContractError> {
+        let (msg, _) = MockFixtures.instantiate();
+        let any_client_state = Any::decode(msg.client_state.as_slice()).unwrap();
+        let any_consensus_state = Any::decode(msg.consensus_state.as_slice()).unwrap();
+        keeper.create_client(any_client_state, any_consensus_state, latest_height)
+    }
+
+    fn mock_keeper() -> WasmKeeper<MockEntrypoint> {
+        let (msg, block) = MockFixtures.instantiate();
+        let mut keeper = WasmKeeper::new(MockLightClient, msg.checksum.to_vec());
+        keeper.set_block(block);
+        keeper
+    }
+
+    #[test]
+    fn test_create_update_and_verify() {
+        let mut keeper = mock_keeper();
+        let client_id = create_client(&mut keeper, LcpHeight::new(0, INITIAL_HEIGHT)).unwrap();
+        assert_eq!(client_id, format!("{WASM_CLIENT_TYPE}-0"));
+        assert_eq!(keeper.status(&client_id).unwrap(), ACTIVE);
+
+        let (msg, block) = MockFixtures.update();
+        keeper.set_block(block.clone());
+        let height = LcpHeight::new(0, INITIAL_HEIGHT + 1);
+        assert_eq!(
+            keeper
+                .update_client(&client_id, msg.client_message)
+                .unwrap(),
+            ClientUpdate::Updated(vec![height])
+        );
+        assert_eq!(keeper.latest_height(&client_id).unwrap(), height);
+        assert_eq!(
+            keeper.timestamp_at_height(&client_id, height).unwrap(),
+            block.time.nanos()
+        );
+
+        let (msg, block) = MockFixtures.membership().unwrap();
+        keeper.set_block(block);
+        keeper
+            .verify_membership(
+                &client_id,
+                height,
+                msg.proof.clone(),
+                msg.merkle_path.clone(),
+                msg.value.clone(),
+            )
+            .unwrap();
+        assert!(keeper
+            .verify_membership(
+                &client_id,
+                height,
+                msg.proof,
+                msg.merkle_path,
+                b"other value".to_vec().into(),
+            )
+            .is_err());
+
+        let (msg, _) = MockFixtures.non_membership().unwrap();
+        keeper
+            .verify_non_membership(&client_id, height, msg.proof, msg.merkle_path)
+            .unwrap();
+
+        // proofs above the latest height are rejected before calling the contract
+        let (msg, _) = MockFixtures.non_membership().unwrap();
+        assert!(keeper
+            .verify_non_membership(
+                &client_id,
+                LcpHeight::new(0, INITIAL_HEIGHT + 2),
+                msg.proof,
+                msg.merkle_path,
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_create_client_at_another_height() {
+        let mut keeper = mock_keeper();
+        let res = create_client(&mut keeper, LcpHeight::new(0, INITIAL_HEIGHT + 1));
+        assert!(res.is_err());
+        assert!(keeper
+            .client_records(&format!("{WASM_CLIENT_TYPE}-0"))
+            .is_empty());
+
+        // the client id is not consumed by the failed creation
+        let client_id = create_client(&mut keeper, LcpHeight::new(0, INITIAL_HEIGHT)).unwrap();
+        assert_eq!(client_id, format!("{WASM_CLIENT_TYPE}-0"));
+    }
+
+    #[test]
+    fn test_failed_sudo_is_discarded() {
+        let (msg, block) = MockFixtures.instantiate();
+        let mut keeper =
+            WasmKeeper::<FailingEntrypoint>::new(MockLightClient, msg.checksum.to_vec());
+        keeper.set_block(block);
+        let height = LcpHeight::new(0, INITIAL_HEIGHT);
+        let client_id = create_client(&mut keeper, height).unwrap();
+
+        let records = keeper.client_records(&client_id);
+        assert!(!records.is_empty());
+        let merkle_path = MerklePath {
+            key_path: vec![b"ibc".to_vec().into(), b"path".to_vec().into()],
+        };
+        assert!(keeper
+            .verify_non_membership(&client_id, height, Binary::default(), merkle_path)
+            .is_err());
+        assert_eq!(keeper.client_records(&client_id), records);
+        assert_eq!(keeper.status(&client_id).unwrap(), ACTIVE);
+    }
+}
@@ -0,0 +1,235 @@
+//! An `Entrypoint` over the mock light client of lcp, and its `Fixtures`.
+
+use crate::context::Context;
+use crate::entrypoint::Entrypoint;
+use crate::error::ContractError;
+use crate::msg::{
+    InstantiateMsg, MerklePath, NoCustomQueryMsg, SudoMsg, UpdateStateMsg,
+    UpdateStateOnMisbehaviourMsg, VerifyMembershipMsg, VerifyNonMembershipMsg,
+};
+use crate::response::ContractResult;
+use crate::testkit::Fixtures;
+use cosmwasm_std::testing::mock_env;
+use cosmwasm_std::{to_json_binary, BlockInfo, DepsMut, Env, Response, Timestamp};
+use ibc::core::ics02_client::client_state::ClientState as _;
+use ibc::core::ics02_client::consensus_state::ConsensusState as _;
+use ibc::mock::client_state::MockClientState;
+use ibc::mock::consensus_state::MockConsensusState;
+use ibc::mock::header::MockHeader;
+use ibc::timestamp::Timestamp as IbcTimestamp;
+use ibc::Height as IbcHeight;
+use light_client::types::proto::google::protobuf::Any as ProtoAny;
+use light_client::types::{Any, Height as LcpHeight};
+use light_client::ClientReader;
+use mock_lc::MockLightClient;
+use prost::Message;
+
+const ACTIVE: &str = "Active";
+const FROZEN: &str = "Frozen";
+
+/// The height at which `MockFixtures` instantiates the client.
+pub const INITIAL_HEIGHT: u64 = 1;
+
+/// The seconds between two blocks of the host chain and of the mock chain.
+const BLOCK_INTERVAL_SECS: u64 = 6;
+
+pub struct MockEntrypoint;
+
+impl Entrypoint for MockEntrypoint {
+    type LightClient = MockLightClient;
+    type CustomQueryMsg = NoCustomQueryMsg;
+
+    fn get_status_from_client_state(any_client_state: Any) -> Result<String, ContractError> {
+        let client_state = MockClientState::try_from(any_client_state.to_proto())?;
+        let status = if client_state.is_frozen() {
+            FROZEN
+        } else {
+            ACTIVE
+        };
+        Ok(status.to_owned())
+    }
+
+    fn get_timestamp_from_consensus_state(any_consensus_state: Any) -> Result<u64, ContractError> {
+        let consensus_state = MockConsensusState::try_from(any_consensus_state.to_proto())?;
+        Ok(consensus_state.timestamp().nanoseconds())
+    }
+
+    /// Replaces the subject client with any substitute which is not frozen.
+    fn check_substitute_and_update_state(
+        subject_any_client_state: Any,
+        substitute_any_client_state: Any,
+    ) -> Result<Any, ContractError> {
+        MockClientState::try_from(subject_any_client_state.to_proto())?;
+        let substitute_client_state =
+            MockClientState::try_from(substitute_any_client_state.clone().to_proto())?;
+        if substitute_client_state.is_frozen() {
+            return Err(ContractError::generic("the substitute client is frozen"));
+        }
+        Ok(substitute_any_client_state)
+    }
+
+    /// Verifies membership against the proofs of `MockFixtures::proof`, as the mock
+    /// chain has no commitment store to prove against.
+    fn sudo(
+        lc: &Self::LightClient,
+        deps: DepsMut<'_>,
+        env: Env,
+        msg: SudoMsg,
+    ) -> Result<Response, ContractError> {
+        let ctx = Context::new_mut(deps, env);
+        let (height, proof, expected_proof) = match msg {
+            SudoMsg::VerifyMembership(msg) => {
+                let (_, path) = msg.merkle_path.prefix_and_path()?;
+                let expected_proof = MockFixtures::proof(&path, Some(msg.value.as_slice()));
+                (msg.height, msg.proof, expected_proof)
+            }
+            SudoMsg::VerifyNonMembership(msg) => {
+                let (_, path) = msg.merkle_path.prefix_and_path()?;
+                let expected_proof = MockFixtures::proof(&path, None);
+                (msg.height, msg.proof, expected_proof)
+            }
+            msg => return Self::sudo_with_context(lc, ctx, msg),
+        };
+
+        ctx.ensure_not_frozen()?;
+        ctx.consensus_state(ctx.client_id(), &height.into())?;
+        if proof.as_slice() != expected_proof.as_slice() {
+            return Err(ContractError::generic("invalid mock proof"));
+        }
+        Ok(Response::default().set_data(to_json_binary(&ContractResult::success())?))
+    }
+}
+
+/// The fixtures of a mock client, whose headers are accepted without verification.
+pub struct MockFixtures;
+
+impl MockFixtures {
+    /// Returns the mock header at `height`, timestamped with the time of `block(height)`.
+    pub fn header(height: u64) -> MockHeader {
+        let timestamp = IbcTimestamp::from_nanoseconds(Self::block(height).time.nanos())
+            .expect("invalid timestamp");
+        MockHeader::new(IbcHeight::new(0, height).expect("invalid height"))
+            .with_timestamp(timestamp)
+    }
+
+    /// Returns the `UpdateState` of the mock header at `height`, and the host block at
+    /// which it is submitted.
+    pub fn update_at(height: u64) -> (UpdateStateMsg, BlockInfo) {
+        let any_header: ProtoAny = Self::header(height).into();
+        let msg = UpdateStateMsg {
+            client_message: any_header.encode_to_vec().into(),
+        };
+        (msg, Self::block(height))
+    }
+
+    /// Returns the `UpdateState` of a mock header at `height` whose timestamp differs
+    /// from `header(height)`, so that its consensus state conflicts with it.
+    pub fn conflicting_update_at(height: u64) -> (UpdateStateMsg, BlockInfo) {
+        let any_header: ProtoAny = Self::conflicting_header(height).into();
+        let msg = UpdateStateMsg {
+            client_message: any_header.encode_to_vec().into(),
+        };
+        (msg, Self::block(height))
+    }
+
+    /// Returns the mock header at `height`, timestamped a nanosecond after `header(height)`.
+    pub fn conflicting_header(height: u64) -> MockHeader {
+        let timestamp = IbcTimestamp::from_nanoseconds(Self::block(height).time.nanos() + 1)
+            .expect("invalid timestamp");
+        MockHeader::new(IbcHeight::new(0, height).expect("invalid height"))
+            .with_timestamp(timestamp)
+    }
+
+    /// Returns the mock proof that `path` holds `value`, or that it is absent if `value`
+    /// is `None`.
+    pub fn proof(path: &str, value: Option<&[u8]>) -> Vec<u8> {
+        match value {
+            Some(value) => [b"membership/".as_slice(), path.as_bytes(), b"/", value].concat(),
+            None => [b"non-membership/".as_slice(), path.as_bytes()].concat(),
+        }
+    }
+
+    fn merkle_path(path: &str) -> MerklePath {
+        MerklePath {
+            key_path: vec![b"ibc".to_vec().into(), path.as_bytes().to_vec().into()],
+        }
+    }
+
+    /// Returns the host block at which the mock header at `height` is submitted.
+    pub fn block(height: u64) -> BlockInfo {
+        let mut block = mock_env().block;
+        block.height += height;
+        block.time = Timestamp::from_seconds(1_700_000_000 + height * BLOCK_INTERVAL_SECS);
+        block
+    }
+}
+
+impl Fixtures for MockFixtures {
+    type Entrypoint = MockEntrypoint;
+
+    fn light_client(&self) -> MockLightClient {
+        MockLightClient
+    }
+
+    fn instantiate(&self) -> (InstantiateMsg, BlockInfo) {
+        let header = Self::header(INITIAL_HEIGHT);
+        let any_client_state: ProtoAny = MockClientState::new(header).into();
+        let any_consensus_state: ProtoAny = MockConsensusState::new(header).into();
+        let msg = InstantiateMsg {
+            client_state: any_client_state.encode_to_vec().into(),
+            consensus_state: any_consensus_state.encode_to_vec().into(),
+            checksum: vec![0; 32].into(),
+        };
+        (msg, Self::block(INITIAL_HEIGHT))
+    }
+
+    fn update(&self) -> (UpdateStateMsg, BlockInfo) {
+        Self::update_at(INITIAL_HEIGHT + 1)
+    }
+
+    /// A header conflicting with the consensus state of the instantiated client.
+    fn misbehaviour(&self) -> Option<(UpdateStateOnMisbehaviourMsg, BlockInfo)> {
+        let (msg, _) = Self::conflicting_update_at(INITIAL_HEIGHT);
+        let msg = UpdateStateOnMisbehaviourMsg {
+            client_message: msg.client_message,
+        };
+        Some((msg, Self::block(INITIAL_HEIGHT + 1)))
+    }
+
+    fn membership(&self) -> Option<(VerifyMembershipMsg, BlockInfo)> {
+        let path = "clients/07-tendermint-0/clientState";
+        let value = b"client state";
+        let msg = VerifyMembershipMsg {
+            height: LcpHeight::new(0, INITIAL_HEIGHT + 1).into(),
+            delay_time_period: 0,
+            delay_block_period: 0,
+            proof: Self::proof(path, Some(value.as_slice())).into(),
+            merkle_path: Self::merkle_path(path),
+            value: value.to_vec().into(),
+        };
+        Some((msg, Self::block(INITIAL_HEIGHT + 1)))
+    }
+
+    fn non_membership(&self) -> Option<(VerifyNonMembershipMsg, BlockInfo)> {
+        let path = "clients/07-tendermint-1/clientState";
+        let msg = VerifyNonMembershipMsg {
+            height: LcpHeight::new(0, INITIAL_HEIGHT + 1).into(),
+            delay_time_period: 0,
+            delay_block_period: 0,
+            proof: Self::proof(path, None).into(),
+            merkle_path: Self::merkle_path(path),
+        };
+        Some((msg, Self::block(INITIAL_HEIGHT + 1)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit::run_all;
+
+    #[test]
+    fn test_run_all() {
+        run_all(&MockFixtures);
+    }
+}
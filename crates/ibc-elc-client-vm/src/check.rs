@@ -0,0 +1,83 @@
+use crate::error::HarnessError;
+use cosmwasm_vm::AnalysisReport;
+use std::collections::HashSet;
+use wasmparser::{Parser, Payload};
+
+/// The maximum size of a contract accepted by the 08-wasm module of ibc-go.
+pub const MAX_WASM_SIZE: usize = 3 * 1024 * 1024;
+
+/// The entry points called by the 08-wasm module of ibc-go.
+pub const REQUIRED_ENTRY_POINTS: &[&str] = &["instantiate", "sudo", "query", "migrate"];
+
+/// The capabilities available to light client contracts on the host chain.
+pub const DEFAULT_CAPABILITIES: &[&str] = &[
+    "iterator",
+    "stargate",
+    "cosmwasm_1_1",
+    "cosmwasm_1_2",
+    "cosmwasm_1_3",
+    "cosmwasm_1_4",
+    "cosmwasm_2_0",
+    "cosmwasm_2_1",
+    "cosmwasm_2_2",
+];
+
+pub fn default_capabilities() -> HashSet<String> {
+    DEFAULT_CAPABILITIES.iter().map(|c| c.to_string()).collect()
+}
+
+/// Checks that the contract can be stored by the 08-wasm module.
+pub fn check_size(wasm: &[u8]) -> Result<(), HarnessError> {
+    if wasm.len() > MAX_WASM_SIZE {
+        return Err(HarnessError::CodeTooLarge {
+            size: wasm.len(),
+            limit: MAX_WASM_SIZE,
+        });
+    }
+    Ok(())
+}
+
+/// Checks that the contract exports every entry point called by ibc-go.
+pub fn check_entry_points(report: &AnalysisReport) -> Result<(), HarnessError> {
+    let entry_points: HashSet<String> = report.entrypoints.iter().map(|e| e.to_string()).collect();
+    for entry_point in REQUIRED_ENTRY_POINTS {
+        if !entry_points.contains(*entry_point) {
+            return Err(HarnessError::MissingEntryPoint(entry_point));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the contract requires no capability beyond `available_capabilities`.
+pub fn check_capabilities(
+    report: &AnalysisReport,
+    available_capabilities: &HashSet<String>,
+) -> Result<(), HarnessError> {
+    match report
+        .required_capabilities
+        .iter()
+        .find(|c| !available_capabilities.contains(*c))
+    {
+        Some(c) => Err(HarnessError::UnavailableCapability(c.clone())),
+        None => Ok(()),
+    }
+}
+
+/// Checks that the contract contains no floating-point operator.
+///
+/// The VM accepts floats with canonicalized NaNs, but a light client has no use for them
+/// and they usually come from a dependency compiled with an unintended feature.
+pub fn check_no_floats(wasm: &[u8]) -> Result<(), HarnessError> {
+    for payload in Parser::new(0).parse_all(wasm) {
+        if let Payload::CodeSectionEntry(body) = payload? {
+            let mut reader = body.get_operators_reader()?;
+            while !reader.eof() {
+                let operator = format!("{:?}", reader.read()?);
+                if operator.contains("F32") || operator.contains("F64") {
+                    return Err(HarnessError::FloatOperator(operator));
+                }
+            }
+        }
+    }
+    Ok(())
+}
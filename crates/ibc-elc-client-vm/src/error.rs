@@ -0,0 +1,81 @@
+use cosmwasm_std::StdError;
+use cosmwasm_vm::VmError;
+use std::fmt::{Debug, Display, Formatter, Result};
+
+#[derive(Debug)]
+pub enum HarnessError {
+    Io(std::io::Error),
+    Std(StdError),
+    Vm(VmError),
+    WasmParse(wasmparser::BinaryReaderError),
+    /// The contract returned an error.
+    Contract(String),
+    /// The contract aborted, typically because of a panic.
+    Aborted(String),
+    MissingEntryPoint(&'static str),
+    UnavailableCapability(String),
+    FloatOperator(String),
+    CodeTooLarge {
+        size: usize,
+        limit: usize,
+    },
+    GasLimitExceeded {
+        used: u64,
+        limit: u64,
+    },
+}
+
+impl Display for HarnessError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Io(e) => write!(f, "HarnessError::Io({})", e),
+            Self::Std(e) => write!(f, "HarnessError::Std({})", e),
+            Self::Vm(e) => write!(f, "HarnessError::Vm({})", e),
+            Self::WasmParse(e) => write!(f, "HarnessError::WasmParse({})", e),
+            Self::Contract(e) => write!(f, "HarnessError::Contract({})", e),
+            Self::Aborted(e) => write!(f, "HarnessError::Aborted({})", e),
+            Self::MissingEntryPoint(e) => write!(f, "HarnessError::MissingEntryPoint({})", e),
+            Self::UnavailableCapability(e) => {
+                write!(f, "HarnessError::UnavailableCapability({})", e)
+            }
+            Self::FloatOperator(e) => write!(f, "HarnessError::FloatOperator({})", e),
+            Self::CodeTooLarge { size, limit } => write!(
+                f,
+                "HarnessError::CodeTooLarge(size={}, limit={})",
+                size, limit
+            ),
+            Self::GasLimitExceeded { used, limit } => write!(
+                f,
+                "HarnessError::GasLimitExceeded(used={}, limit={})",
+                used, limit
+            ),
+        }
+    }
+}
+
+impl From<std::io::Error> for HarnessError {
+    fn from(v: std::io::Error) -> Self {
+        Self::Io(v)
+    }
+}
+
+impl From<StdError> for HarnessError {
+    fn from(v: StdError) -> Self {
+        Self::Std(v)
+    }
+}
+
+impl From<VmError> for HarnessError {
+    fn from(v: VmError) -> Self {
+        match v {
+            VmError::Aborted { msg, .. } => Self::Aborted(msg),
+            v => Self::Vm(v),
+        }
+    }
+}
+
+impl From<wasmparser::BinaryReaderError> for HarnessError {
+    fn from(v: wasmparser::BinaryReaderError) -> Self {
+        Self::WasmParse(v)
+    }
+}
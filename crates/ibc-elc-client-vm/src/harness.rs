@@ -0,0 +1,328 @@
+use crate::check::{
+    check_capabilities, check_entry_points, check_no_floats, check_size, default_capabilities,
+};
+use crate::error::HarnessError;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::testing::{message_info, mock_env};
+use cosmwasm_std::{
+    from_json, to_json_vec, Addr, Binary, BlockInfo, Checksum, ContractResult, Empty, Order,
+    Record, Response,
+};
+use cosmwasm_vm::testing::{mock_backend, MockApi, MockQuerier, MockStorage};
+use cosmwasm_vm::{
+    call_instantiate, call_migrate, call_query, call_sudo, AnalysisReport, Backend, Cache,
+    CacheOptions, Instance, InstanceOptions, Size, Storage, VmError, VmResult,
+};
+use ibc_elc_client_cw::msg::*;
+use ibc_elc_client_cw::testkit::{env, Fixtures, CLIENT_ID};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// The number of VM gas units per SDK gas unit, as configured by wasmd.
+pub const GAS_MULTIPLIER: u64 = 140_000;
+/// The gas limit of a call, in VM gas units.
+pub const DEFAULT_GAS_LIMIT: u64 = 3_000_000 * GAS_MULTIPLIER;
+pub const DEFAULT_MEMORY_LIMIT: Size = Size::mebi(32);
+
+const MEMORY_CACHE_SIZE: Size = Size::mebi(64);
+
+type MockInstance = Instance<MockApi, MockStorage, MockQuerier>;
+
+/// Returns the path of a contract built with
+/// `cargo build --release --target wasm32-unknown-unknown`.
+pub fn artifact_path(name: &str) -> PathBuf {
+    let target_dir = std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("../../target"));
+    target_dir
+        .join("wasm32-unknown-unknown/release")
+        .join(format!("{name}.wasm"))
+}
+
+/// Returns the path of the `FixtureFlow` of a contract, captured from a chain with the
+/// relayer of its client.
+pub fn fixture_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(format!("{name}.json"))
+}
+
+/// The messages of the test kit fixtures of a client, each with the host block at which
+/// it is sent.
+#[cw_serde]
+pub struct FixtureFlow {
+    pub instantiate: (InstantiateMsg, BlockInfo),
+    pub update: (UpdateStateMsg, BlockInfo),
+    pub membership: Option<(VerifyMembershipMsg, BlockInfo)>,
+    pub non_membership: Option<(VerifyNonMembershipMsg, BlockInfo)>,
+    pub misbehaviour: Option<(UpdateStateOnMisbehaviourMsg, BlockInfo)>,
+}
+
+impl FixtureFlow {
+    pub fn from_fixtures<F: Fixtures>(fixtures: &F) -> Self {
+        Self {
+            instantiate: fixtures.instantiate(),
+            update: fixtures.update(),
+            membership: fixtures.membership(),
+            non_membership: fixtures.non_membership(),
+            misbehaviour: fixtures.misbehaviour(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, HarnessError> {
+        Ok(from_json(std::fs::read(path)?)?)
+    }
+}
+
+/// A step of a flow run by `VmHarness::run_flow`.
+#[derive(Clone, Debug)]
+pub enum Step {
+    Instantiate(InstantiateMsg),
+    Sudo(SudoMsg),
+    Query(QueryMsg),
+}
+
+/// Runs a compiled contract in `cosmwasm-vm` with a mock backend, as the client
+/// `CLIENT_ID` of the 08-wasm module.
+///
+/// The contract is stored through the same `Cache` as wasmvm, which rejects contracts
+/// with missing exports or unavailable capabilities, once checked against the size limit
+/// of 08-wasm. Every call is metered against the gas limit and runs with the memory limit
+/// of the host chain.
+pub struct VmHarness {
+    _base_dir: TempDir,
+    cache: Cache<MockApi, MockStorage, MockQuerier>,
+    checksum: Checksum,
+    report: AnalysisReport,
+    backend: Option<Backend<MockApi, MockStorage, MockQuerier>>,
+    block: BlockInfo,
+    gas_limit: u64,
+    last_gas_used: u64,
+    max_gas_used: u64,
+}
+
+impl VmHarness {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, HarnessError> {
+        Self::load_with_capabilities(path, default_capabilities())
+    }
+
+    pub fn load_with_capabilities(
+        path: impl AsRef<Path>,
+        available_capabilities: HashSet<String>,
+    ) -> Result<Self, HarnessError> {
+        let wasm = std::fs::read(path)?;
+        check_size(&wasm)?;
+        check_no_floats(&wasm)?;
+
+        let base_dir = TempDir::new()?;
+        let options = CacheOptions::new(
+            base_dir.path(),
+            available_capabilities.clone(),
+            MEMORY_CACHE_SIZE,
+            DEFAULT_MEMORY_LIMIT,
+        );
+        // SAFETY: the cache directory is private to this harness
+        let cache = unsafe { Cache::new(options)? };
+        let checksum = cache.save_wasm(&wasm)?;
+        let report = cache.analyze(&checksum)?;
+        check_entry_points(&report)?;
+        check_capabilities(&report, &available_capabilities)?;
+
+        Ok(Self {
+            _base_dir: base_dir,
+            cache,
+            checksum,
+            report,
+            backend: Some(mock_backend(&[])),
+            block: mock_env().block,
+            gas_limit: DEFAULT_GAS_LIMIT,
+            last_gas_used: 0,
+            max_gas_used: 0,
+        })
+    }
+
+    pub fn checksum(&self) -> &Checksum {
+        &self.checksum
+    }
+
+    pub fn report(&self) -> &AnalysisReport {
+        &self.report
+    }
+
+    pub fn set_block(&mut self, block: BlockInfo) {
+        self.block = block;
+    }
+
+    pub fn set_gas_limit(&mut self, gas_limit: u64) {
+        self.gas_limit = gas_limit;
+    }
+
+    /// Returns the gas used by the last call, in VM gas units.
+    pub fn last_gas_used(&self) -> u64 {
+        self.last_gas_used
+    }
+
+    /// Returns the maximum gas used by a call so far, in VM gas units.
+    pub fn max_gas_used(&self) -> u64 {
+        self.max_gas_used
+    }
+
+    pub fn storage(&self) -> &MockStorage {
+        &self
+            .backend
+            .as_ref()
+            .expect("the backend is recycled after every call")
+            .storage
+    }
+
+    pub fn instantiate(&mut self, msg: &InstantiateMsg) -> Result<Response, HarnessError> {
+        let env = env(self.block.clone());
+        let info = message_info(&Addr::unchecked(CLIENT_ID), &[]);
+        let msg = to_json_vec(msg)?;
+        self.call(|instance| call_instantiate::<_, _, _, Empty>(instance, &env, &info, &msg))
+    }
+
+    pub fn sudo(&mut self, msg: &SudoMsg) -> Result<Response, HarnessError> {
+        let env = env(self.block.clone());
+        let msg = to_json_vec(msg)?;
+        self.call(|instance| call_sudo::<_, _, _, Empty>(instance, &env, &msg))
+    }
+
+    pub fn query(&mut self, msg: &QueryMsg) -> Result<Binary, HarnessError> {
+        let env = env(self.block.clone());
+        let msg = to_json_vec(msg)?;
+        self.call(|instance| call_query(instance, &env, &msg))
+    }
+
+    pub fn migrate(&mut self, msg: &MigrateMsg) -> Result<Response, HarnessError> {
+        let env = env(self.block.clone());
+        let msg = to_json_vec(msg)?;
+        self.call(|instance| call_migrate::<_, _, _, Empty>(instance, &env, &msg))
+    }
+
+    /// Runs the steps in order, each at its block, and returns the gas used by each step.
+    pub fn run_flow(
+        &mut self,
+        steps: impl IntoIterator<Item = (Step, BlockInfo)>,
+    ) -> Result<Vec<u64>, HarnessError> {
+        let mut gas_used = vec![];
+        for (step, block) in steps {
+            self.set_block(block);
+            match step {
+                Step::Instantiate(msg) => {
+                    self.instantiate(&msg)?;
+                }
+                Step::Sudo(msg) => {
+                    self.sudo(&msg)?;
+                }
+                Step::Query(msg) => {
+                    self.query(&msg)?;
+                }
+            }
+            gas_used.push(self.last_gas_used);
+        }
+        Ok(gas_used)
+    }
+
+    /// Runs the flow of the test kit fixtures: instantiate, update, membership
+    /// verification, and misbehaviour.
+    pub fn run_fixtures(&mut self, flow: &FixtureFlow) -> Result<Vec<u64>, HarnessError> {
+        let FixtureFlow {
+            instantiate: (instantiate, block),
+            update: (update, update_block),
+            membership,
+            non_membership,
+            misbehaviour,
+        } = flow.clone();
+        let mut steps = vec![
+            (Step::Instantiate(instantiate), block.clone()),
+            (Step::Query(QueryMsg::Status(StatusMsg {})), block),
+        ];
+        let block = update_block;
+        steps.push((
+            Step::Query(QueryMsg::VerifyClientMessage(VerifyClientMessageMsg {
+                client_message: update.client_message.clone(),
+            })),
+            block.clone(),
+        ));
+        steps.push((
+            Step::Query(QueryMsg::CheckForMisbehaviour(CheckForMisbehaviourMsg {
+                client_message: update.client_message.clone(),
+            })),
+            block.clone(),
+        ));
+        steps.push((Step::Sudo(SudoMsg::UpdateState(update)), block));
+        if let Some((msg, block)) = membership {
+            steps.push((Step::Sudo(SudoMsg::VerifyMembership(msg)), block));
+        }
+        if let Some((msg, block)) = non_membership {
+            steps.push((Step::Sudo(SudoMsg::VerifyNonMembership(msg)), block));
+        }
+        if let Some((msg, block)) = misbehaviour {
+            steps.push((Step::Sudo(SudoMsg::UpdateStateOnMisbehaviour(msg)), block));
+        }
+        self.run_flow(steps)
+    }
+
+    fn call<T>(
+        &mut self,
+        f: impl FnOnce(&mut MockInstance) -> VmResult<ContractResult<T>>,
+    ) -> Result<T, HarnessError> {
+        let mut backend = self
+            .backend
+            .take()
+            .expect("the backend is recycled after every call");
+        let options = InstanceOptions {
+            gas_limit: self.gas_limit,
+        };
+        // the backend is dropped if no instance is created, so its store is rebuilt
+        let records = match all_records(&mut backend.storage) {
+            Ok(records) => records,
+            Err(e) => {
+                self.backend = Some(backend);
+                return Err(e.into());
+            }
+        };
+        let mut instance = match self.cache.get_instance(&self.checksum, backend, options) {
+            Ok(instance) => instance,
+            Err(e) => {
+                self.backend = Some(backend_with_records(records)?);
+                return Err(e.into());
+            }
+        };
+        let res = f(&mut instance);
+
+        let report = instance.create_gas_report();
+        self.backend = instance.recycle();
+        self.last_gas_used = report.used_internally + report.used_externally;
+        self.max_gas_used = self.max_gas_used.max(self.last_gas_used);
+
+        match res {
+            Err(VmError::GasDepletion { .. }) => Err(HarnessError::GasLimitExceeded {
+                used: self.last_gas_used,
+                limit: self.gas_limit,
+            }),
+            res => res?.into_result().map_err(HarnessError::Contract),
+        }
+    }
+}
+
+fn all_records(storage: &mut MockStorage) -> VmResult<Vec<Record>> {
+    let iterator_id = storage.scan(None, None, Order::Ascending).0?;
+    let mut records = vec![];
+    while let Some(record) = storage.next(iterator_id).0? {
+        records.push(record);
+    }
+    Ok(records)
+}
+
+fn backend_with_records(
+    records: Vec<Record>,
+) -> VmResult<Backend<MockApi, MockStorage, MockQuerier>> {
+    let mut backend = mock_backend(&[]);
+    for (key, value) in records {
+        backend.storage.set(&key, &value).0?;
+    }
+    Ok(backend)
+}
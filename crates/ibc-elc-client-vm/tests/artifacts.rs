@@ -0,0 +1,133 @@
+//! Runs the contracts built with `cargo build --release --target wasm32-unknown-unknown`
+//! in the VM, with the gas and memory limits of the host chain.
+//!
+//! The flows of the contracts run the fixtures in `tests/fixtures`, captured from a chain
+//! with the relayer of each client.
+
+use cosmwasm_std::{from_json, to_json_vec};
+use ibc_elc_client_cw::msg::{InstantiateMsg, QueryMsg, StatusMsg};
+use ibc_elc_client_cw::response::StatusResponse;
+use ibc_elc_client_cw::testkit::mock::MockFixtures;
+use ibc_elc_client_vm::check::{check_size, MAX_WASM_SIZE};
+use ibc_elc_client_vm::error::HarnessError;
+use ibc_elc_client_vm::harness::{
+    artifact_path, fixture_path, FixtureFlow, VmHarness, DEFAULT_GAS_LIMIT,
+};
+
+const ARTIFACTS: &[&str] = &["ibc_parlia_client_cw", "ibc_ethereum_client_cw"];
+
+fn load(name: &str) -> VmHarness {
+    VmHarness::load(artifact_path(name)).unwrap_or_else(|e| panic!("failed to load {name}: {e}"))
+}
+
+fn load_fixtures(name: &str) -> FixtureFlow {
+    FixtureFlow::load(fixture_path(name))
+        .unwrap_or_else(|e| panic!("failed to load the fixtures of {name}: {e}"))
+}
+
+fn status(harness: &mut VmHarness) -> String {
+    let res = harness.query(&QueryMsg::Status(StatusMsg {})).unwrap();
+    from_json::<StatusResponse>(res).unwrap().status
+}
+
+fn invalid_instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        client_state: b"garbage".to_vec().into(),
+        consensus_state: b"garbage".to_vec().into(),
+        checksum: vec![0; 32].into(),
+    }
+}
+
+#[test]
+#[ignore = "requires the contracts built for wasm32-unknown-unknown"]
+fn test_invalid_messages_are_contract_errors() {
+    for name in ARTIFACTS {
+        let mut harness = load(name);
+
+        let res = harness.instantiate(&invalid_instantiate_msg());
+        assert!(
+            matches!(res, Err(HarnessError::Contract(_))),
+            "{name}: {res:?}"
+        );
+        assert!(harness.last_gas_used() <= DEFAULT_GAS_LIMIT, "{name}");
+
+        let res = harness.query(&QueryMsg::Status(StatusMsg {}));
+        assert!(
+            matches!(res, Err(HarnessError::Contract(_))),
+            "{name}: {res:?}"
+        );
+        assert!(
+            harness.max_gas_used() <= DEFAULT_GAS_LIMIT,
+            "{name}: {}",
+            harness.max_gas_used()
+        );
+    }
+}
+
+#[test]
+#[ignore = "requires the contracts built for wasm32-unknown-unknown"]
+fn test_gas_limit() {
+    for name in ARTIFACTS {
+        let mut harness = load(name);
+        harness.set_gas_limit(1);
+        let res = harness.instantiate(&invalid_instantiate_msg());
+        assert!(
+            matches!(res, Err(HarnessError::GasLimitExceeded { limit: 1, .. })),
+            "{name}: {res:?}"
+        );
+
+        // the harness keeps working after running out of gas
+        harness.set_gas_limit(DEFAULT_GAS_LIMIT);
+        let res = harness.instantiate(&invalid_instantiate_msg());
+        assert!(
+            matches!(res, Err(HarnessError::Contract(_))),
+            "{name}: {res:?}"
+        );
+    }
+}
+
+#[test]
+#[ignore = "requires the contracts built for wasm32-unknown-unknown and their fixtures"]
+fn test_fixture_flows() {
+    for name in ARTIFACTS {
+        let flow = load_fixtures(name);
+        let mut harness = load(name);
+        let gas_used = harness
+            .run_fixtures(&flow)
+            .unwrap_or_else(|e| panic!("{name}: {e}"));
+        assert!(
+            gas_used.iter().all(|gas| *gas <= DEFAULT_GAS_LIMIT),
+            "{name}: {gas_used:?}"
+        );
+
+        let expected = if flow.misbehaviour.is_some() {
+            "Frozen"
+        } else {
+            "Active"
+        };
+        assert_eq!(status(&mut harness), expected, "{name}");
+    }
+}
+
+#[test]
+fn test_fixture_flow_json() {
+    let flow = FixtureFlow::from_fixtures(&MockFixtures);
+    let json = to_json_vec(&flow).unwrap();
+    assert_eq!(from_json::<FixtureFlow>(json).unwrap(), flow);
+}
+
+#[test]
+fn test_check_size() {
+    assert!(check_size(&vec![0; MAX_WASM_SIZE]).is_ok());
+    let res = check_size(&vec![0; MAX_WASM_SIZE + 1]);
+    assert!(
+        matches!(
+            res,
+            Err(HarnessError::CodeTooLarge {
+                limit: MAX_WASM_SIZE,
+                ..
+            })
+        ),
+        "{res:?}"
+    );
+}
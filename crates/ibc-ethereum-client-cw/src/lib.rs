@@ -1,25 +1,35 @@
-use cosmwasm_std::{entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response};
+pub mod msg;
+pub mod preset;
+
+use crate::msg::{EthereumQueryMsg, SyncCommitteeMsg, SyncCommitteeResponse};
+use crate::preset::{Preset, MAINNET_SYNC_COMMITTEE_SIZE, MINIMAL_SYNC_COMMITTEE_SIZE};
+use cosmwasm_std::{
+    entry_point, to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response,
+};
 use ethereum_elc::client::EthereumLightClient;
 use ethereum_elc::ibc::{client_state::ClientState, consensus_state::ConsensusState};
 use ibc::core::ics02_client::client_state::ClientState as _;
+use ibc_elc_client_cw::backend::BackendMut;
+use ibc_elc_client_cw::context::Context;
 use ibc_elc_client_cw::entrypoint::Entrypoint;
 use ibc_elc_client_cw::error::ContractError;
-use ibc_elc_client_cw::msg::{InstantiateMsg, QueryMsg, SudoMsg};
+use ibc_elc_client_cw::msg::{ContractQueryMsg, InstantiateMsg, MigrateMsg, SudoMsg};
 use light_client::types::Any;
-
-const SYNC_COMMITTEE_SIZE: usize = if cfg!(feature = "minimal") {
-    ethereum_elc::ibc::consensus::preset::minimal::PRESET.SYNC_COMMITTEE_SIZE
-} else {
-    ethereum_elc::ibc::consensus::preset::mainnet::PRESET.SYNC_COMMITTEE_SIZE
-};
+use light_client::{ClientReader, LightClient};
+use prost::Message;
+use std::time::Duration;
 
 const ACTIVE: &str = "Active";
 const FROZEN: &str = "Frozen";
 
-struct EthereumEntrypoint;
+struct EthereumEntrypoint<const SYNC_COMMITTEE_SIZE: usize>;
 
-impl Entrypoint for EthereumEntrypoint {
+type MainnetEntrypoint = EthereumEntrypoint<MAINNET_SYNC_COMMITTEE_SIZE>;
+type MinimalEntrypoint = EthereumEntrypoint<MINIMAL_SYNC_COMMITTEE_SIZE>;
+
+impl<const SYNC_COMMITTEE_SIZE: usize> Entrypoint for EthereumEntrypoint<SYNC_COMMITTEE_SIZE> {
     type LightClient = EthereumLightClient<SYNC_COMMITTEE_SIZE>;
+    type CustomQueryMsg = EthereumQueryMsg;
 
     fn get_status_from_client_state(any_client_state: Any) -> Result<String, ContractError> {
         let client_state: ClientState<SYNC_COMMITTEE_SIZE> =
@@ -36,6 +46,60 @@ impl Entrypoint for EthereumEntrypoint {
         let consensus_state: ConsensusState = any_consensus_state.to_proto().try_into()?;
         Ok(consensus_state.timestamp.nanoseconds())
     }
+
+    fn get_trusting_period_from_client_state(
+        any_client_state: Any,
+    ) -> Result<Option<Duration>, ContractError> {
+        let client_state: ClientState<SYNC_COMMITTEE_SIZE> =
+            any_client_state.to_proto().try_into()?;
+        Ok(Some(client_state.trusting_period))
+    }
+
+    fn custom_query<B: BackendMut>(
+        lc: &Self::LightClient,
+        ctx: &Context<B>,
+        msg: Self::CustomQueryMsg,
+    ) -> Result<Binary, ContractError> {
+        let retval = match msg {
+            EthereumQueryMsg::SyncCommittee(SyncCommitteeMsg {}) => {
+                let client_state: ClientState<SYNC_COMMITTEE_SIZE> =
+                    ctx.client_state(ctx.client_id())?.to_proto().try_into()?;
+                let latest_height = lc.latest_height(ctx, ctx.client_id())?;
+                let consensus_state: ConsensusState = ctx
+                    .consensus_state(ctx.client_id(), &latest_height)?
+                    .to_proto()
+                    .try_into()?;
+
+                let finalized_slot = u64::from(consensus_state.slot);
+                let slots_per_period = u64::from(client_state.slots_per_epoch)
+                    * u64::from(client_state.epochs_per_sync_committee_period);
+                let (current_sync_committee_period, next_sync_committee_period_start_slot) =
+                    sync_committee_periods(finalized_slot, slots_per_period).ok_or_else(|| {
+                        ContractError::generic(format!(
+                            "invalid sync committee period: {slots_per_period} slots"
+                        ))
+                    })?;
+                to_json_binary(&SyncCommitteeResponse {
+                    finalized_slot,
+                    finalized_execution_block_number: client_state
+                        .latest_height()
+                        .revision_height(),
+                    current_sync_committee_period,
+                    next_sync_committee_period: current_sync_committee_period + 1,
+                    next_sync_committee_period_start_slot,
+                    next_sync_committee_known: consensus_state.next_sync_committee.is_some(),
+                })?
+            }
+        };
+        Ok(retval)
+    }
+}
+
+/// Returns the sync committee period of `slot` and the first slot of the next period.
+fn sync_committee_periods(slot: u64, slots_per_period: u64) -> Option<(u64, u64)> {
+    let period = slot.checked_div(slots_per_period)?;
+    let next_period_start_slot = (period + 1).checked_mul(slots_per_period)?;
+    Some((period, next_period_start_slot))
 }
 
 #[entry_point]
@@ -45,21 +109,99 @@ pub fn instantiate(
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    EthereumEntrypoint::instantiate(
-        &EthereumLightClient::<SYNC_COMMITTEE_SIZE>,
-        deps,
-        env,
-        info,
-        msg,
-    )
+    let any_client_state = Any::decode(msg.client_state.as_slice())?;
+    match Preset::from_client_state(any_client_state)? {
+        Preset::Mainnet => MainnetEntrypoint::instantiate(
+            &EthereumLightClient::<MAINNET_SYNC_COMMITTEE_SIZE>,
+            deps,
+            env,
+            info,
+            msg,
+        ),
+        Preset::Minimal => MinimalEntrypoint::instantiate(
+            &EthereumLightClient::<MINIMAL_SYNC_COMMITTEE_SIZE>,
+            deps,
+            env,
+            info,
+            msg,
+        ),
+    }
+}
+
+#[entry_point]
+pub fn migrate(deps: DepsMut<'_>, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    MainnetEntrypoint::migrate(deps, env, msg)
 }
 
 #[entry_point]
 pub fn sudo(deps: DepsMut<'_>, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
-    EthereumEntrypoint::sudo(&EthereumLightClient::<SYNC_COMMITTEE_SIZE>, deps, env, msg)
+    // the context keeps the client state decoded here for the entrypoint
+    let mut ctx = Context::new_mut(deps, env);
+    if let SudoMsg::MigrateClientStore(_) = msg {
+        ctx.set_subject_prefix();
+    }
+    match Preset::from_context(&ctx)? {
+        Preset::Mainnet => MainnetEntrypoint::sudo_with_context(
+            &EthereumLightClient::<MAINNET_SYNC_COMMITTEE_SIZE>,
+            ctx,
+            msg,
+        ),
+        Preset::Minimal => MinimalEntrypoint::sudo_with_context(
+            &EthereumLightClient::<MINIMAL_SYNC_COMMITTEE_SIZE>,
+            ctx,
+            msg,
+        ),
+    }
 }
 
 #[entry_point]
-pub fn query(deps: Deps<'_>, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
-    EthereumEntrypoint::query(&EthereumLightClient::<SYNC_COMMITTEE_SIZE>, deps, env, msg)
+pub fn query(
+    deps: Deps<'_>,
+    env: Env,
+    msg: ContractQueryMsg<EthereumQueryMsg>,
+) -> Result<Binary, ContractError> {
+    let ctx = Context::new_ref(deps, env);
+    match Preset::from_context(&ctx)? {
+        Preset::Mainnet => MainnetEntrypoint::query_with_context(
+            &EthereumLightClient::<MAINNET_SYNC_COMMITTEE_SIZE>,
+            ctx,
+            msg,
+        ),
+        Preset::Minimal => MinimalEntrypoint::query_with_context(
+            &EthereumLightClient::<MINIMAL_SYNC_COMMITTEE_SIZE>,
+            ctx,
+            msg,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_committee_periods() {
+        // 32 slots per epoch and 256 epochs per period on mainnet
+        let slots_per_period = 32 * 256;
+        for (slot, expected) in [
+            (0, (0, 8192)),
+            (8191, (0, 8192)),
+            (8192, (1, 16384)),
+            (8193, (1, 16384)),
+            (16383, (1, 16384)),
+            (16384, (2, 24576)),
+        ] {
+            assert_eq!(
+                sync_committee_periods(slot, slots_per_period),
+                Some(expected),
+                "{slot}"
+            );
+        }
+        // 8 slots per epoch and 8 epochs per period on minimal
+        assert_eq!(sync_committee_periods(63, 8 * 8), Some((0, 64)));
+        assert_eq!(sync_committee_periods(64, 8 * 8), Some((1, 128)));
+
+        assert_eq!(sync_committee_periods(1, 0), None);
+        assert_eq!(sync_committee_periods(u64::MAX, slots_per_period), None);
+    }
 }
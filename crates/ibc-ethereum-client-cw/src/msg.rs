@@ -0,0 +1,34 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+
+// ------------------------------------------------------------
+// Implementation of the EthereumQueryMsg enum and its variants
+// ------------------------------------------------------------
+
+#[derive(QueryResponses)]
+#[cw_serde]
+pub enum EthereumQueryMsg {
+    #[returns(SyncCommitteeResponse)]
+    SyncCommittee(SyncCommitteeMsg),
+}
+
+#[cw_serde]
+pub struct SyncCommitteeMsg {}
+
+// ------------------------------------------------------------
+// Implementation of the responses
+// ------------------------------------------------------------
+
+#[cw_serde]
+pub struct SyncCommitteeResponse {
+    /// The slot of the finalized beacon block of the latest consensus state.
+    pub finalized_slot: u64,
+    /// The execution block number of the finalized beacon block, i.e. the latest height.
+    pub finalized_execution_block_number: u64,
+    pub current_sync_committee_period: u64,
+    pub next_sync_committee_period: u64,
+    /// The first slot of the next sync committee period. An update with the next sync
+    /// committee must be submitted before the client reaches the period after it.
+    pub next_sync_committee_period_start_slot: u64,
+    /// Whether the next sync committee is stored in the latest consensus state.
+    pub next_sync_committee_known: bool,
+}
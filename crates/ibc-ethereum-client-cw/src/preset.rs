@@ -0,0 +1,48 @@
+use ethereum_elc::ibc::client_state::ClientState;
+use ethereum_elc::ibc::consensus::preset::{mainnet, minimal};
+use ibc_elc_client_cw::backend::BackendMut;
+use ibc_elc_client_cw::context::Context;
+use ibc_elc_client_cw::error::ContractError;
+use light_client::types::Any;
+use light_client::ClientReader;
+
+pub const MAINNET_SYNC_COMMITTEE_SIZE: usize = mainnet::PRESET.SYNC_COMMITTEE_SIZE;
+pub const MINIMAL_SYNC_COMMITTEE_SIZE: usize = minimal::PRESET.SYNC_COMMITTEE_SIZE;
+
+/// The consensus preset of the chain tracked by a client.
+///
+/// Mainnet, Sepolia and Holesky use the mainnet preset, and devnets usually use the
+/// minimal one. The preset is selected from the chain configuration recorded in the
+/// client state, so that a single contract can serve clients of both.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    Mainnet,
+    Minimal,
+}
+
+impl Preset {
+    pub fn from_client_state(any_client_state: Any) -> Result<Self, ContractError> {
+        // the sync committee size does not affect the decoding of the client state
+        let client_state: ClientState<MAINNET_SYNC_COMMITTEE_SIZE> =
+            any_client_state.to_proto().try_into()?;
+        if client_state.slots_per_epoch == mainnet::PRESET.SLOTS_PER_EPOCH
+            && client_state.epochs_per_sync_committee_period
+                == mainnet::PRESET.EPOCHS_PER_SYNC_COMMITTEE_PERIOD
+        {
+            Ok(Self::Mainnet)
+        } else if client_state.slots_per_epoch == minimal::PRESET.SLOTS_PER_EPOCH
+            && client_state.epochs_per_sync_committee_period
+                == minimal::PRESET.EPOCHS_PER_SYNC_COMMITTEE_PERIOD
+        {
+            Ok(Self::Minimal)
+        } else {
+            Err(ContractError::generic(
+                "client state does not match the mainnet or minimal preset",
+            ))
+        }
+    }
+
+    pub fn from_context<B: BackendMut>(ctx: &Context<B>) -> Result<Self, ContractError> {
+        Self::from_client_state(ctx.client_state(ctx.client_id())?)
+    }
+}
@@ -0,0 +1,89 @@
+use cosmwasm_std::{entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response};
+use ibc_elc_client_cw::entrypoint::Entrypoint;
+use ibc_elc_client_cw::error::ContractError;
+use ibc_elc_client_cw::msg::{
+    ContractQueryMsg, InstantiateMsg, MigrateMsg, NoCustomQueryMsg, SudoMsg,
+};
+use light_client::types::Any;
+use optimism_elc::client::OptimismLightClient;
+use optimism_elc::client_state::ClientState;
+use optimism_elc::consensus_state::ConsensusState;
+use std::time::Duration;
+
+const ACTIVE: &str = "Active";
+const FROZEN: &str = "Frozen";
+
+struct OptimismEntrypoint;
+
+impl Entrypoint for OptimismEntrypoint {
+    type LightClient = OptimismLightClient;
+    type CustomQueryMsg = NoCustomQueryMsg;
+
+    fn get_status_from_client_state(any_client_state: Any) -> Result<String, ContractError> {
+        let client_state: ClientState = any_client_state
+            .try_into()
+            .map_err(ContractError::generic)?;
+        let status = if client_state.frozen { FROZEN } else { ACTIVE };
+        Ok(status.to_owned())
+    }
+
+    fn get_timestamp_from_consensus_state(any_consensus_state: Any) -> Result<u64, ContractError> {
+        let consensus_state: ConsensusState = any_consensus_state
+            .try_into()
+            .map_err(ContractError::generic)?;
+        let timestamp: u64 = consensus_state
+            .timestamp
+            .as_unix_timestamp_nanos()
+            .try_into()?;
+        Ok(timestamp)
+    }
+
+    fn get_trusting_period_from_client_state(
+        any_client_state: Any,
+    ) -> Result<Option<Duration>, ContractError> {
+        let client_state: ClientState = any_client_state
+            .try_into()
+            .map_err(ContractError::generic)?;
+        Ok(Some(client_state.trusting_period))
+    }
+}
+
+#[entry_point]
+pub fn instantiate(
+    deps: DepsMut<'_>,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    OptimismEntrypoint::instantiate(&OptimismLightClient, deps, env, info, msg)
+}
+
+#[entry_point]
+pub fn migrate(deps: DepsMut<'_>, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    OptimismEntrypoint::migrate(deps, env, msg)
+}
+
+#[entry_point]
+pub fn sudo(deps: DepsMut<'_>, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    OptimismEntrypoint::sudo(&OptimismLightClient, deps, env, msg)
+}
+
+#[entry_point]
+pub fn query(
+    deps: Deps<'_>,
+    env: Env,
+    msg: ContractQueryMsg<NoCustomQueryMsg>,
+) -> Result<Binary, ContractError> {
+    OptimismEntrypoint::query(&OptimismLightClient, deps, env, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ibc_elc_client_cw::testkit::check_foreign_states_are_errors;
+
+    #[test]
+    fn test_foreign_states_are_errors() {
+        check_foreign_states_are_errors::<OptimismEntrypoint>();
+    }
+}
@@ -1,11 +1,21 @@
-use cosmwasm_std::{entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response};
+pub mod msg;
+
+use crate::msg::{EpochMsg, EpochResponse, ParliaQueryMsg};
+use cosmwasm_std::{
+    entry_point, to_json_binary, Binary, Deps, DepsMut, Env, HexBinary, MessageInfo, Response,
+};
+use ibc_elc_client_cw::backend::BackendMut;
+use ibc_elc_client_cw::context::Context;
 use ibc_elc_client_cw::entrypoint::Entrypoint;
 use ibc_elc_client_cw::error::ContractError;
-use ibc_elc_client_cw::msg::{InstantiateMsg, QueryMsg, SudoMsg};
+use ibc_elc_client_cw::msg::{ContractQueryMsg, InstantiateMsg, MigrateMsg, SudoMsg};
 use light_client::types::Any;
+use light_client::{ClientReader, LightClient};
 use parlia_elc::client::ParliaLightClient;
 use parlia_elc::client_state::ClientState;
 use parlia_elc::consensus_state::ConsensusState;
+use parlia_elc::fork_spec::find_target_fork_spec;
+use std::time::Duration;
 
 const ACTIVE: &str = "Active";
 const FROZEN: &str = "Frozen";
@@ -14,6 +24,7 @@ struct ParliaEntrypoint;
 
 impl Entrypoint for ParliaEntrypoint {
     type LightClient = ParliaLightClient;
+    type CustomQueryMsg = ParliaQueryMsg;
 
     fn get_status_from_client_state(any_client_state: Any) -> Result<String, ContractError> {
         let client_state: ClientState = any_client_state
@@ -33,6 +44,75 @@ impl Entrypoint for ParliaEntrypoint {
             .try_into()?;
         Ok(timestamp)
     }
+
+    fn get_trusting_period_from_client_state(
+        any_client_state: Any,
+    ) -> Result<Option<Duration>, ContractError> {
+        let client_state: ClientState = any_client_state
+            .try_into()
+            .map_err(ContractError::generic)?;
+        Ok(Some(client_state.trusting_period))
+    }
+
+    fn custom_query<B: BackendMut>(
+        lc: &Self::LightClient,
+        ctx: &Context<B>,
+        msg: Self::CustomQueryMsg,
+    ) -> Result<Binary, ContractError> {
+        let retval = match msg {
+            ParliaQueryMsg::Epoch(EpochMsg {}) => {
+                let latest_height = lc.latest_height(ctx, ctx.client_id())?;
+                let client_state: ClientState = ctx
+                    .client_state(ctx.client_id())?
+                    .try_into()
+                    .map_err(ContractError::generic)?;
+                let consensus_state: ConsensusState = ctx
+                    .consensus_state(ctx.client_id(), &latest_height)?
+                    .try_into()
+                    .map_err(ContractError::generic)?;
+
+                // The epoch length changes with the hard forks, e.g. 500 at Lorentz
+                // and 1000 at Maxwell.
+                let latest_height = latest_height.revision_height();
+                let latest_timestamp: u64 =
+                    (consensus_state.timestamp.as_unix_timestamp_nanos() / 1_000_000).try_into()?;
+                let epoch_length = find_target_fork_spec(
+                    &client_state.fork_specs,
+                    latest_height,
+                    latest_timestamp,
+                )
+                .map_err(ContractError::generic)?
+                .epoch_length;
+                let (current_epoch_height, next_epoch_height) =
+                    epoch_heights(latest_height, epoch_length).ok_or_else(|| {
+                        ContractError::generic(format!("invalid epoch length: {epoch_length}"))
+                    })?;
+                to_json_binary(&EpochResponse {
+                    latest_height,
+                    current_validators_hash: HexBinary::from(
+                        consensus_state.current_validators_hash.to_vec(),
+                    ),
+                    previous_validators_hash: HexBinary::from(
+                        consensus_state.previous_validators_hash.to_vec(),
+                    ),
+                    epoch_length,
+                    current_epoch_height,
+                    next_epoch_height,
+                })?
+            }
+        };
+        Ok(retval)
+    }
+}
+
+/// Returns the height of the epoch block of `height` and the height of the next
+/// epoch block, at which the validator set changes.
+fn epoch_heights(height: u64, epoch_length: u64) -> Option<(u64, u64)> {
+    let current_epoch_height = height - height.checked_rem(epoch_length)?;
+    Some((
+        current_epoch_height,
+        current_epoch_height.checked_add(epoch_length)?,
+    ))
 }
 
 #[entry_point]
@@ -45,12 +125,48 @@ pub fn instantiate(
     ParliaEntrypoint::instantiate(&ParliaLightClient, deps, env, info, msg)
 }
 
+#[entry_point]
+pub fn migrate(deps: DepsMut<'_>, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    ParliaEntrypoint::migrate(deps, env, msg)
+}
+
 #[entry_point]
 pub fn sudo(deps: DepsMut<'_>, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
     ParliaEntrypoint::sudo(&ParliaLightClient, deps, env, msg)
 }
 
 #[entry_point]
-pub fn query(deps: Deps<'_>, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+pub fn query(
+    deps: Deps<'_>,
+    env: Env,
+    msg: ContractQueryMsg<ParliaQueryMsg>,
+) -> Result<Binary, ContractError> {
     ParliaEntrypoint::query(&ParliaLightClient, deps, env, msg)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_epoch_heights() {
+        for (height, epoch_length, expected) in [
+            (0, 500, (0, 500)),
+            (1, 500, (0, 500)),
+            (499, 500, (0, 500)),
+            (500, 500, (500, 1000)),
+            (501, 500, (500, 1000)),
+            (999, 1000, (0, 1000)),
+            (1000, 1000, (1000, 2000)),
+            (1001, 1000, (1000, 2000)),
+        ] {
+            assert_eq!(
+                epoch_heights(height, epoch_length),
+                Some(expected),
+                "{height} {epoch_length}"
+            );
+        }
+        assert_eq!(epoch_heights(500, 0), None);
+        assert_eq!(epoch_heights(u64::MAX, 1000), None);
+    }
+}
@@ -0,0 +1,35 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::HexBinary;
+
+// ------------------------------------------------------------
+// Implementation of the ParliaQueryMsg enum and its variants
+// ------------------------------------------------------------
+
+#[derive(QueryResponses)]
+#[cw_serde]
+pub enum ParliaQueryMsg {
+    #[returns(EpochResponse)]
+    Epoch(EpochMsg),
+}
+
+#[cw_serde]
+pub struct EpochMsg {}
+
+// ------------------------------------------------------------
+// Implementation of the responses
+// ------------------------------------------------------------
+
+#[cw_serde]
+pub struct EpochResponse {
+    /// The latest height of the client.
+    pub latest_height: u64,
+    /// The hash of the validator set trusted for the epoch of the latest height.
+    pub current_validators_hash: HexBinary,
+    /// The hash of the validator set trusted for the epoch before the latest height.
+    pub previous_validators_hash: HexBinary,
+    pub epoch_length: u64,
+    /// The height of the epoch block of the latest height.
+    pub current_epoch_height: u64,
+    /// The height of the next epoch block, at which the validator set changes.
+    pub next_epoch_height: u64,
+}
@@ -0,0 +1,14 @@
+#![no_main]
+
+use ibc_elc_client_cw::entrypoint::Entrypoint;
+use ibc_elc_client_cw_fuzz::{setup, FuzzQueryMsg, MockEntrypoint};
+use libfuzzer_sys::fuzz_target;
+use mock_lc::MockLightClient;
+
+fuzz_target!(|msg: FuzzQueryMsg| {
+    let Some(msg) = msg.into_msg() else {
+        return;
+    };
+    let (deps, env) = setup();
+    let _ = MockEntrypoint::query(&MockLightClient, deps.as_ref(), env, msg);
+});
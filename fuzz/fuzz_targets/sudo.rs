@@ -0,0 +1,20 @@
+#![no_main]
+
+use ibc_elc_client_cw::entrypoint::Entrypoint;
+use ibc_elc_client_cw_fuzz::{records, setup, FuzzSudoMsg, MockEntrypoint};
+use libfuzzer_sys::fuzz_target;
+use mock_lc::MockLightClient;
+
+fuzz_target!(|msg: FuzzSudoMsg| {
+    let Some(msg) = msg.into_msg() else {
+        return;
+    };
+    let (mut deps, env) = setup();
+    let before = records(&deps.storage);
+    if MockEntrypoint::sudo(&MockLightClient, deps.as_mut(), env, msg).is_err() {
+        assert!(
+            before == records(&deps.storage),
+            "a failed sudo call must not write to the client store"
+        );
+    }
+});
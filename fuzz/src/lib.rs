@@ -0,0 +1,225 @@
+//! Shared setup of the fuzz targets: a mock client instantiated over an in-memory
+//! store, and arbitrary `SudoMsg`/`QueryMsg` payloads.
+
+use arbitrary::Arbitrary;
+use cosmwasm_std::testing::{message_info, mock_dependencies, MockApi, MockQuerier, MockStorage};
+use cosmwasm_std::{from_json, Addr, Binary, Env, Order, OwnedDeps, Record, Storage};
+use ibc_elc_client_cw::entrypoint::Entrypoint;
+use ibc_elc_client_cw::msg::*;
+use ibc_elc_client_cw::testkit::mock::MockFixtures;
+use ibc_elc_client_cw::testkit::{env, Fixtures, CLIENT_ID};
+use light_client::types::Any;
+use mock_lc::MockLightClient;
+use prost::Message;
+
+pub use ibc_elc_client_cw::testkit::mock::MockEntrypoint;
+
+/// The type URLs an arbitrary protobuf payload is wrapped with, so that the payload
+/// reaches the light client rather than failing to decode as an `Any`.
+const TYPE_URLS: &[&str] = &[
+    "/ibc.mock.ClientState",
+    "/ibc.mock.ConsensusState",
+    "/ibc.mock.Header",
+    "/ibc.mock.Misbehaviour",
+    "/ibc.lightclients.wasm.v1.ClientState",
+    "/ibc.lightclients.wasm.v1.ConsensusState",
+];
+
+pub type Deps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+/// Returns the dependencies and the environment of the mock client instantiated by
+/// the test kit fixtures.
+pub fn setup() -> (Deps, Env) {
+    let (msg, block) = MockFixtures.instantiate();
+    let mut deps = mock_dependencies();
+    let env = env(block);
+    MockEntrypoint::instantiate(
+        &MockLightClient,
+        deps.as_mut(),
+        env.clone(),
+        message_info(&Addr::unchecked(CLIENT_ID), &[]),
+        msg,
+    )
+    .expect("failed to instantiate the mock client");
+    (deps, env)
+}
+
+/// Returns every record of the store, to check that failed calls do not write.
+pub fn records(storage: &MockStorage) -> Vec<Record> {
+    storage.range(None, None, Order::Ascending).collect()
+}
+
+/// An attacker-controlled byte payload: either raw bytes, or a protobuf `Any` wrapping
+/// raw bytes with a known type URL.
+#[derive(Arbitrary, Debug)]
+pub enum Payload {
+    Raw(Vec<u8>),
+    Any { type_url: u8, value: Vec<u8> },
+}
+
+impl From<Payload> for Binary {
+    fn from(v: Payload) -> Self {
+        match v {
+            Payload::Raw(bytes) => bytes.into(),
+            Payload::Any { type_url, value } => {
+                let type_url = TYPE_URLS[usize::from(type_url) % TYPE_URLS.len()];
+                Any::new(type_url.to_owned(), value).encode_to_vec().into()
+            }
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+pub struct FuzzHeight {
+    revision_number: u64,
+    revision_height: u64,
+}
+
+impl From<FuzzHeight> for Height {
+    fn from(v: FuzzHeight) -> Self {
+        Self {
+            revision_number: v.revision_number,
+            revision_height: v.revision_height,
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+pub enum FuzzSudoMsg {
+    /// A JSON message, dropped if it does not decode as a `SudoMsg`.
+    Json(Vec<u8>),
+    UpdateState(Payload),
+    UpdateStateOnMisbehaviour(Payload),
+    VerifyMembership {
+        height: FuzzHeight,
+        proof: Payload,
+        key_path: Vec<Vec<u8>>,
+        value: Vec<u8>,
+    },
+    VerifyNonMembership {
+        height: FuzzHeight,
+        proof: Payload,
+        key_path: Vec<Vec<u8>>,
+    },
+    MigrateClientStore,
+}
+
+impl FuzzSudoMsg {
+    pub fn into_msg(self) -> Option<SudoMsg> {
+        let msg = match self {
+            Self::Json(bytes) => return from_json(bytes).ok(),
+            Self::UpdateState(client_message) => SudoMsg::UpdateState(UpdateStateMsg {
+                client_message: client_message.into(),
+            }),
+            Self::UpdateStateOnMisbehaviour(client_message) => {
+                SudoMsg::UpdateStateOnMisbehaviour(UpdateStateOnMisbehaviourMsg {
+                    client_message: client_message.into(),
+                })
+            }
+            Self::VerifyMembership {
+                height,
+                proof,
+                key_path,
+                value,
+            } => SudoMsg::VerifyMembership(VerifyMembershipMsg {
+                height: height.into(),
+                delay_time_period: 0,
+                delay_block_period: 0,
+                proof: proof.into(),
+                merkle_path: merkle_path(key_path),
+                value: value.into(),
+            }),
+            Self::VerifyNonMembership {
+                height,
+                proof,
+                key_path,
+            } => SudoMsg::VerifyNonMembership(VerifyNonMembershipMsg {
+                height: height.into(),
+                delay_time_period: 0,
+                delay_block_period: 0,
+                proof: proof.into(),
+                merkle_path: merkle_path(key_path),
+            }),
+            Self::MigrateClientStore => SudoMsg::MigrateClientStore(MigrateClientStoreMsg {}),
+        };
+        Some(msg)
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+pub enum FuzzQueryMsg {
+    /// A JSON message, dropped if it does not decode as a `ContractQueryMsg`.
+    Json(Vec<u8>),
+    Status,
+    TimestampAtHeight(FuzzHeight),
+    VerifyClientMessage(Payload),
+    CheckForMisbehaviour(Payload),
+    UpdateMeta(FuzzHeight),
+    ConsensusStateHeights {
+        start_after: Option<FuzzHeight>,
+        limit: Option<u32>,
+    },
+    ClientState,
+    ConsensusState(FuzzHeight),
+    MisbehaviourEvidence,
+    SimulateUpdateState(Payload),
+    ClientHealth,
+    UpdateHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+impl FuzzQueryMsg {
+    pub fn into_msg(self) -> Option<ContractQueryMsg<NoCustomQueryMsg>> {
+        let msg = match self {
+            Self::Json(bytes) => return from_json(bytes).ok(),
+            Self::Status => QueryMsg::Status(StatusMsg {}),
+            Self::TimestampAtHeight(height) => QueryMsg::TimestampAtHeight(TimestampAtHeightMsg {
+                height: height.into(),
+            }),
+            Self::VerifyClientMessage(client_message) => {
+                QueryMsg::VerifyClientMessage(VerifyClientMessageMsg {
+                    client_message: client_message.into(),
+                })
+            }
+            Self::CheckForMisbehaviour(client_message) => {
+                QueryMsg::CheckForMisbehaviour(CheckForMisbehaviourMsg {
+                    client_message: client_message.into(),
+                })
+            }
+            Self::UpdateMeta(height) => QueryMsg::UpdateMeta(UpdateMetaMsg {
+                height: height.into(),
+            }),
+            Self::ConsensusStateHeights { start_after, limit } => {
+                QueryMsg::ConsensusStateHeights(ConsensusStateHeightsMsg {
+                    start_after: start_after.map(Into::into),
+                    limit,
+                })
+            }
+            Self::ClientState => QueryMsg::ClientState(ClientStateMsg {}),
+            Self::ConsensusState(height) => QueryMsg::ConsensusState(ConsensusStateMsg {
+                height: height.into(),
+            }),
+            Self::MisbehaviourEvidence => {
+                QueryMsg::MisbehaviourEvidence(MisbehaviourEvidenceMsg {})
+            }
+            Self::SimulateUpdateState(client_message) => {
+                QueryMsg::SimulateUpdateState(SimulateUpdateStateMsg {
+                    client_message: client_message.into(),
+                })
+            }
+            Self::ClientHealth => QueryMsg::ClientHealth(ClientHealthMsg {}),
+            Self::UpdateHistory { start_after, limit } => {
+                QueryMsg::UpdateHistory(UpdateHistoryMsg { start_after, limit })
+            }
+        };
+        Some(ContractQueryMsg::Wasm(msg))
+    }
+}
+
+fn merkle_path(key_path: Vec<Vec<u8>>) -> MerklePath {
+    MerklePath {
+        key_path: key_path.into_iter().map(Into::into).collect(),
+    }
+}